@@ -0,0 +1,259 @@
+use crate::{Bounds, Shape, Vec2};
+
+// [Broadphase]
+// The world is cut into square cells and every object is filed under the cells
+// its bounding box touches. Only objects sharing a cell are handed to the SAT
+// narrowphase. Cells wrap around the screen edges like the bodies do, so an
+// object poking out on one side is also found near the opposite side. They are
+// about `CELL` wide, stretched so that a whole number of them spans the screen.
+const CELL: f32 = 128.0;
+
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    // Inside out for an object without parts, it then covers no cell at all.
+    pub fn of(object: &impl Shape) -> Aabb {
+        let mut aabb = Aabb {
+            min: Vec2::splat(f32::MAX),
+            max: Vec2::splat(f32::MIN),
+        };
+        for part in object.parts() {
+            let (min, max) = part.extent();
+            aabb.min = aabb.min.min(min);
+            aabb.max = aabb.max.max(max);
+        }
+        aabb
+    }
+
+    // Also covers where the object was before moving by `travel`.
+    pub fn swept(object: &impl Shape, travel: Vec2) -> Aabb {
+        let aabb = Aabb::of(object);
+        Aabb {
+            min: aabb.min.min(aabb.min - travel),
+            max: aabb.max.max(aabb.max - travel),
+        }
+    }
+}
+
+pub struct Grid {
+    columns: i32,
+    rows: i32,
+    // a cell's width and height, the last column ends exactly on the edge
+    cell: Vec2,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(bounds: Bounds) -> Grid {
+        let columns = (bounds.width / CELL).ceil().max(1.0) as i32;
+        let rows = (bounds.height / CELL).ceil().max(1.0) as i32;
+        Grid {
+            columns,
+            rows,
+            cell: Vec2::new(bounds.width / columns as f32, bounds.height / rows as f32),
+            cells: vec![vec![]; (columns * rows) as usize],
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, aabb: &Aabb) {
+        for cell in self.cells_of(aabb) {
+            self.cells[cell].push(index);
+        }
+    }
+
+    // Everything filed under the cells `aabb` touches, sorted and without repeats.
+    pub fn query(&self, aabb: &Aabb, found: &mut Vec<usize>) {
+        found.clear();
+        for cell in self.cells_of(aabb) {
+            found.extend_from_slice(&self.cells[cell]);
+        }
+        found.sort_unstable();
+        found.dedup();
+    }
+
+    fn cells_of(&self, aabb: &Aabb) -> Vec<usize> {
+        let (x0, x1) = span(aabb.min.x, aabb.max.x, self.cell.x, self.columns);
+        let (y0, y1) = span(aabb.min.y, aabb.max.y, self.cell.y, self.rows);
+        let mut cells = Vec::with_capacity(((x1 - x0 + 1) * (y1 - y0 + 1)) as usize);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let column = x.rem_euclid(self.columns);
+                let row = y.rem_euclid(self.rows);
+                cells.push((row * self.columns + column) as usize);
+            }
+        }
+        cells
+    }
+}
+
+// First and last cell covered along one axis, never more than once around.
+fn span(min: f32, max: f32, cell: f32, count: i32) -> (i32, i32) {
+    if min > max {
+        return (0, -1);
+    }
+    let first = (min / cell).floor() as i32;
+    let last = (max / cell).floor() as i32;
+    (first, last.min(first.saturating_add(count - 1)))
+}
+
+// Pairs `(i, j)` of `first[i]` and `second[j]` that may touch, ordered by `i`
+// then `j` so the narrowphase visits them in the same order every run.
+pub fn candidates(
+    first: &[impl Shape],
+    second: &[impl Shape],
+    bounds: Bounds,
+) -> Vec<(usize, usize)> {
+    let first: Vec<Aabb> = first.iter().map(Aabb::of).collect();
+    let second: Vec<Aabb> = second.iter().map(Aabb::of).collect();
+    overlapping(&first, &second, bounds)
+}
+
+// Same as `candidates` for boxes built by the caller, like swept ones.
+pub fn overlapping(first: &[Aabb], second: &[Aabb], bounds: Bounds) -> Vec<(usize, usize)> {
+    let mut grid = Grid::new(bounds);
+    for (it, aabb) in second.iter().enumerate() {
+        grid.insert(it, aabb);
+    }
+
+    let mut pairs = vec![];
+    let mut found = vec![];
+    for (it, aabb) in first.iter().enumerate() {
+        grid.query(aabb, &mut found);
+        pairs.extend(found.iter().map(|other| (it, *other)));
+    }
+    pairs
+}
+
+// Pairs `(i, j)` with `i < j` of `objects` that may touch each other.
+pub fn self_candidates(objects: &[impl Shape], bounds: Bounds) -> Vec<(usize, usize)> {
+    let aabbs: Vec<Aabb> = objects.iter().map(Aabb::of).collect();
+    let mut grid = Grid::new(bounds);
+    for (it, aabb) in aabbs.iter().enumerate() {
+        grid.insert(it, aabb);
+    }
+
+    let mut pairs = vec![];
+    let mut found = vec![];
+    for (it, aabb) in aabbs.iter().enumerate() {
+        grid.query(aabb, &mut found);
+        pairs.extend(
+            found
+                .iter()
+                .filter(|other| **other > it)
+                .map(|other| (it, *other)),
+        );
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{contact, Asteroid, Body, Rng};
+    use std::time::Instant;
+
+    const BOUNDS: Bounds = Bounds {
+        width: 800.0,
+        height: 600.0,
+    };
+
+    fn square(x: f32, y: f32, half: f32) -> Asteroid {
+        let outline = vec![
+            Vec2::new(-half, -half),
+            Vec2::new(half, -half),
+            Vec2::new(half, half),
+            Vec2::new(-half, half),
+        ];
+        let body = Body {
+            lin_pos: Vec2::new(x, y),
+            ..Default::default()
+        };
+        Asteroid::from_outline(body, outline, false)
+    }
+
+    // What the narrowphase was handed before the grid: every pair.
+    fn brute_force(asteroids: &[Asteroid]) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for it in 0..asteroids.len() {
+            for other in it + 1..asteroids.len() {
+                if contact(&asteroids[it], &asteroids[other], BOUNDS).is_some() {
+                    pairs.push((it, other));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn grid(asteroids: &[Asteroid]) -> Vec<(usize, usize)> {
+        self_candidates(asteroids, BOUNDS)
+            .into_iter()
+            .filter(|(it, other)| contact(&asteroids[*it], &asteroids[*other], BOUNDS).is_some())
+            .collect()
+    }
+
+    // A few hundred fragments scattered over the screen, some across the edges.
+    fn fragments(count: usize) -> Vec<Asteroid> {
+        let mut rng = Rng::new(7);
+        (0..count)
+            .map(|_| {
+                let body = Body {
+                    lin_pos: Vec2::new(rng.range(0.0, BOUNDS.width), rng.range(0.0, BOUNDS.height)),
+                    ..Default::default()
+                };
+                Asteroid::jagged(body, rng.range(5.0, 20.0), &mut rng, false)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn objects_without_parts_have_no_candidates() {
+        let empty = Aabb {
+            min: Vec2::splat(f32::MAX),
+            max: Vec2::splat(f32::MIN),
+        };
+        let rock = Aabb::of(&square(400.0, 300.0, 20.0));
+        assert!(overlapping(&[empty], &[empty, rock], BOUNDS).is_empty());
+        assert!(overlapping(&[rock], &[empty], BOUNDS).is_empty());
+    }
+
+    #[test]
+    fn boxes_across_the_right_edge_wrap() {
+        let asteroids = vec![square(790.0, 300.0, 20.0), square(25.0, 300.0, 20.0)];
+        assert_eq!(self_candidates(&asteroids, BOUNDS), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn boxes_across_the_bottom_edge_wrap() {
+        let asteroids = vec![square(400.0, 590.0, 20.0), square(400.0, 25.0, 20.0)];
+        assert_eq!(self_candidates(&asteroids, BOUNDS), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn grid_finds_what_brute_force_finds() {
+        let asteroids = fragments(300);
+        let expected = brute_force(&asteroids);
+        assert!(!expected.is_empty());
+        assert_eq!(grid(&asteroids), expected);
+    }
+
+    // A benchmark rather than a check, timings depend on the machine:
+    // `cargo test --release -- --ignored --nocapture grid_against_brute_force`
+    #[test]
+    #[ignore]
+    fn grid_against_brute_force() {
+        for count in [100, 300, 1000] {
+            let asteroids = fragments(count);
+            let start = Instant::now();
+            brute_force(&asteroids);
+            let brute_force_time = start.elapsed();
+            let start = Instant::now();
+            grid(&asteroids);
+            let grid_time = start.elapsed();
+            println!("{count} fragments: brute force {brute_force_time:?}, grid {grid_time:?}");
+        }
+    }
+}
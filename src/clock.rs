@@ -0,0 +1,117 @@
+use crate::{get_time, is_key_down, is_key_pressed, screen_height, screen_width, KeyCode};
+use crate::{Body, Bounds, GameObjects, History, Input, Tape, TICK};
+
+// [FixedStep]
+// Rendering lags up to one tick behind the simulation and interpolates
+// between the last two ticks. Frames longer than this (window dragged,
+// breakpoint hit) are not caught up on.
+const MAX_FRAME: f32 = 0.25;
+
+pub struct Clock {
+    last_time: f64,
+    accumulator: f32,
+    pending: Input,
+    previous: Option<GameObjects>,
+    pub tape: Tape,
+}
+
+impl Clock {
+    pub fn new(tape: Tape) -> Clock {
+        Clock {
+            last_time: get_time(),
+            accumulator: 0.0,
+            pending: Default::default(),
+            previous: None,
+            tape,
+        }
+    }
+
+    pub fn reset(&mut self, tape: Tape) {
+        *self = Clock::new(tape);
+    }
+
+    // Runs as many ticks as the time since the last frame allows. Presses that
+    // land on a frame without a tick are held back for the next one.
+    pub fn advance(&mut self, history: &mut History, game_objects: &mut GameObjects) {
+        let now = get_time();
+        self.accumulator += ((now - self.last_time) as f32).min(MAX_FRAME);
+        self.last_time = now;
+
+        let input = Input::read();
+        self.pending.fire |= input.fire;
+        self.pending.drop_field |= input.drop_field;
+
+        while self.accumulator >= TICK {
+            if self.tape.is_finished() {
+                self.accumulator = 0.0;
+                break;
+            }
+            let input = self.tape.next(Input {
+                fire: self.pending.fire,
+                drop_field: self.pending.drop_field,
+                ..input
+            });
+            self.previous = Some(game_objects.clone());
+            history.update(game_objects, input, self.tape.replay().bounds, TICK);
+            self.pending = Default::default();
+            self.accumulator -= TICK;
+        }
+    }
+
+    // The world somewhere between the last two ticks, only meant for drawing.
+    pub fn interpolated(&self, game_objects: &GameObjects) -> GameObjects {
+        let mut interpolated = game_objects.clone();
+        let Some(previous) = &self.previous else {
+            return interpolated;
+        };
+        let alpha = self.accumulator / TICK;
+
+        interpolated.ship.body = lerp(&previous.ship.body, &game_objects.ship.body, alpha);
+        if let (Some(previous), Some(shadow)) = (&previous.shadow, &mut interpolated.shadow) {
+            shadow.ship.body = lerp(&previous.ship.body, &shadow.ship.body, alpha);
+        }
+        if previous.asteroids.len() == interpolated.asteroids.len() {
+            for (previous, asteroid) in previous.asteroids.iter().zip(&mut interpolated.asteroids) {
+                asteroid.body = lerp(&previous.body, &asteroid.body, alpha);
+            }
+        }
+        if previous.bullets.len() == interpolated.bullets.len() {
+            for (previous, bullet) in previous.bullets.iter().zip(&mut interpolated.bullets) {
+                bullet.body = lerp(&previous.body, &bullet.body, alpha);
+            }
+        }
+        interpolated
+    }
+}
+
+impl Input {
+    pub fn read() -> Input {
+        Input {
+            thrust: is_key_down(KeyCode::Up) || is_key_down(KeyCode::I),
+            left: is_key_down(KeyCode::Left) || is_key_down(KeyCode::J),
+            right: is_key_down(KeyCode::Right) || is_key_down(KeyCode::L),
+            fire: is_key_pressed(KeyCode::F),
+            drop_field: is_key_pressed(KeyCode::G),
+            rewind: is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::U),
+        }
+    }
+}
+
+pub fn screen_bounds() -> Bounds {
+    Bounds {
+        width: screen_width(),
+        height: screen_height(),
+    }
+}
+
+fn lerp(previous: &Body, current: &Body, alpha: f32) -> Body {
+    // a jump this large means the body warped or was swapped for another one
+    if previous.lin_pos.distance(current.lin_pos) > 100.0 {
+        return current.clone();
+    }
+    Body {
+        lin_pos: previous.lin_pos.lerp(current.lin_pos, alpha),
+        ang_pos: previous.ang_pos + (current.ang_pos - previous.ang_pos) * alpha,
+        ..current.clone()
+    }
+}
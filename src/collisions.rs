@@ -1,69 +1,476 @@
-use crate::Shape;
-use crate::{Mat2, Vec2, TAU};
+use crate::{Body, Bounds, Vec2, TAU};
+use crate::{Primitive, Shape, TimeImmune};
 
-pub fn collision(
-    object1: &impl Shape,
-    object2: &impl Shape,
-) -> Option<[Vec<VerticeCollisionInfo>; 2]> {
-    let mut info1 = Vec::new();
-    let mut info2 = Vec::new();
+// [Narrowphase]
+// Separating axis test between two convex polygons of either winding. The
+// axis of least overlap gives the normal and depth, then the edge of each
+// polygon facing the other is found and the incident edge is clipped against
+// the reference edge to get up to two contact points. Works on slices only, so
+// callers holding vertices already pay for no allocation at all.
+#[derive(Clone, Copy)]
+pub struct Manifold {
+    // from object1 towards object2
+    pub normal: Vec2,
+    pub depth: f32,
+    pub points: [Vec2; 2],
+    pub count: usize,
+    // how far object2 was moved to its wrapped image nearest to object1
+    pub offset: Vec2,
+}
+
+impl Manifold {
+    pub fn points(&self) -> &[Vec2] {
+        &self.points[..self.count]
+    }
+}
+
+// [Warping]
+// The world is a torus, object2 is tested at whichever of its wrapped images
+// lies closest to object1. Concave shapes are tested part by part and the
+// deepest contact wins.
+pub fn manifold(object1: &impl Shape, object2: &impl Shape, bounds: Bounds) -> Option<Manifold> {
+    let parts1 = object1.parts();
+    let mut parts2 = object2.parts();
+    let offset = nearest_image(&parts1, &mut parts2, bounds);
+    let manifold = deepest(&parts1, &parts2)?;
+    Some(Manifold { offset, ..manifold })
+}
+
+fn deepest(parts1: &[Primitive], parts2: &[Primitive]) -> Option<Manifold> {
+    parts1
+        .iter()
+        .flat_map(|part1| parts2.iter().filter_map(move |part2| between(part1, part2)))
+        .max_by(|a, b| a.depth.total_cmp(&b.depth))
+}
+
+// Moves `parts2` next to `parts1`, returns by how much.
+fn nearest_image(parts1: &[Primitive], parts2: &mut [Primitive], bounds: Bounds) -> Vec2 {
+    let middle = |parts: &[Primitive]| {
+        parts.iter().map(Primitive::center).sum::<Vec2>() / parts.len().max(1) as f32
+    };
+    let offset = bounds.nearest_image(middle(parts1), middle(parts2));
+    if offset != Vec2::ZERO {
+        parts2.iter_mut().for_each(|part| part.translate(offset));
+    }
+    offset
+}
+
+pub fn between(primitive1: &Primitive, primitive2: &Primitive) -> Option<Manifold> {
+    match (primitive1, primitive2) {
+        (Primitive::Polygon(vertices1), Primitive::Polygon(vertices2)) => sat(vertices1, vertices2),
+        (Primitive::Polygon(vertices), round) => {
+            let (a, b, radius) = segment(round);
+            let manifold = rounded_polygon(a, b, radius, vertices)?;
+            Some(Manifold {
+                normal: -manifold.normal,
+                ..manifold
+            })
+        }
+        (round, Primitive::Polygon(vertices)) => {
+            let (a, b, radius) = segment(round);
+            rounded_polygon(a, b, radius, vertices)
+        }
+        (round1, round2) => {
+            let (a1, b1, radius1) = segment(round1);
+            let (a2, b2, radius2) = segment(round2);
+            let (point1, point2) = closest_between(a1, b1, a2, b2);
+            let towards2 = (point2 - point1)
+                .try_normalize()
+                .or_else(|| ((a2 + b2) / 2.0 - (a1 + b1) / 2.0).try_normalize())
+                .or_else(|| (b1 - a1).perp().try_normalize())
+                .unwrap_or(Vec2::X);
+            let depth = radius1 + radius2 - point1.distance(point2);
+            if depth <= 0.0 {
+                return None;
+            }
+            Some(Manifold {
+                normal: towards2,
+                depth,
+                points: [point1 + towards2 * (radius1 - depth / 2.0), Vec2::ZERO],
+                count: 1,
+                offset: Vec2::ZERO,
+            })
+        }
+    }
+}
+
+// Segment and radius of a round primitive.
+fn segment(primitive: &Primitive) -> (Vec2, Vec2, f32) {
+    match primitive {
+        Primitive::Circle { center, radius } => (*center, *center, *radius),
+        Primitive::Capsule { a, b, radius } => (*a, *b, *radius),
+        Primitive::Polygon(_) => unreachable!("polygons are not round"),
+    }
+}
+
+// Separating axis test between a capsule (object1) and a convex polygon. On
+// top of the edge normals and the capsule's own normal, the axes from every
+// corner to the nearest point of the segment cover the rounded ends, which
+// makes the test exact.
+fn rounded_polygon(a: Vec2, b: Vec2, radius: f32, vertices: &[Vec2]) -> Option<Manifold> {
+    if vertices.len() < 3 {
+        return None;
+    }
+    let center2 = center(vertices);
+    let capsule = |axis: Vec2| {
+        let (proj_a, proj_b) = (a.dot(axis), b.dot(axis));
+        (proj_a.min(proj_b) - radius, proj_a.max(proj_b) + radius)
+    };
 
-    for (vertice_index, vertice) in object1.shape().iter().enumerate() {
-        if let Some(mtv) = inside(&vertice, object2) {
-            info1.push(VerticeCollisionInfo { vertice_index, mtv });
+    let mut depth = f32::MAX;
+    let mut normal = Vec2::ZERO;
+    let mut test = |axis: Vec2| {
+        if axis == Vec2::ZERO {
+            return true;
+        }
+        let (min1, max1) = capsule(axis);
+        let (min2, max2) = project(vertices, axis);
+        // how far to push the capsule back along or against the axis
+        let (back, forth) = (max1 - min2, max2 - min1);
+        if back <= 0.0 || forth <= 0.0 {
+            return false;
+        }
+        if back.min(forth) < depth {
+            depth = back.min(forth);
+            normal = if back < forth { axis } else { -axis };
+        }
+        true
+    };
+    for it in 0..vertices.len() {
+        let corner = vertices[it];
+        if !test(outward(vertices, it, center2))
+            || !test((corner - closest_on(a, b, corner)).normalize_or_zero())
+        {
+            return None;
         }
     }
+    if !test((b - a).perp().normalize_or_zero()) {
+        return None;
+    }
 
-    for (vertice_index, vertice) in object2.shape().iter().enumerate() {
-        if let Some(mtv) = inside(&vertice, object1) {
-            info2.push(VerticeCollisionInfo { vertice_index, mtv });
+    // the capsule surface furthest into the polygon, both ends when lying
+    // flat on it, kept to the stretch the polygon covers
+    let (proj_a, proj_b) = (a.dot(normal), b.dot(normal));
+    let mut points = [Vec2::ZERO; 2];
+    let count = if a != b && (proj_a - proj_b).abs() < 1e-3 {
+        let along = (b - a).normalize();
+        let (min, max) = project(vertices, along);
+        for (point, end) in points.iter_mut().zip([a, b]) {
+            let proj = end.dot(along);
+            *point = end + along * (proj.clamp(min, max) - proj) + normal * radius;
         }
+        2
+    } else {
+        points[0] = if proj_a > proj_b { a } else { b } + normal * radius;
+        1
+    };
+    Some(Manifold {
+        normal,
+        depth,
+        points,
+        count,
+        offset: Vec2::ZERO,
+    })
+}
+
+fn closest_on(a: Vec2, b: Vec2, point: Vec2) -> Vec2 {
+    let along = b - a;
+    let length_squared = along.length_squared();
+    if length_squared == 0.0 {
+        return a;
     }
+    a + along * ((point - a).dot(along) / length_squared).clamp(0.0, 1.0)
+}
 
-    if info1.is_empty() && info2.is_empty() {
+// Closest points between segments a1-b1 and a2-b2 (Ericson, Real-Time
+// Collision Detection 5.1.9).
+fn closest_between(a1: Vec2, b1: Vec2, a2: Vec2, b2: Vec2) -> (Vec2, Vec2) {
+    let (d1, d2, r) = (b1 - a1, b2 - a2, a1 - a2);
+    let (length1, length2) = (d1.length_squared(), d2.length_squared());
+    let f = d2.dot(r);
+    if length1 <= f32::EPSILON && length2 <= f32::EPSILON {
+        return (a1, a2);
+    }
+    let (s, t) = if length1 <= f32::EPSILON {
+        (0.0, (f / length2).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if length2 <= f32::EPSILON {
+            ((-c / length1).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denominator = length1 * length2 - b * b;
+            let s = if denominator != 0.0 {
+                ((b * f - c * length2) / denominator).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let t = (b * s + f) / length2;
+            if t < 0.0 {
+                ((-c / length1).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / length1).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (a1 + d1 * s, a2 + d2 * t)
+}
+
+pub fn sat(vertices1: &[Vec2], vertices2: &[Vec2]) -> Option<Manifold> {
+    if vertices1.len() < 3 || vertices2.len() < 3 {
         return None;
+    }
+    debug_assert!(
+        is_normalized(vertices1) && is_normalized(vertices2),
+        "narrowphase needs convex positively wound shapes"
+    );
+    let (center1, center2) = (center(vertices1), center(vertices2));
+
+    let mut depth = f32::MAX;
+    let mut normal = Vec2::ZERO;
+    for (vertices, center) in [(vertices1, center1), (vertices2, center2)] {
+        for it in 0..vertices.len() {
+            let axis = outward(vertices, it, center);
+            if axis == Vec2::ZERO {
+                continue;
+            }
+            let (min1, max1) = project(vertices1, axis);
+            let (min2, max2) = project(vertices2, axis);
+            let overlap = max1.min(max2) - min1.max(min2);
+            if overlap <= 0.0 {
+                return None;
+            }
+            if overlap < depth {
+                depth = overlap;
+                normal = axis;
+            }
+        }
+    }
+    if normal.dot(center2 - center1) < 0.0 {
+        normal = -normal;
+    }
+
+    let edge1 = facing_edge(vertices1, normal);
+    let edge2 = facing_edge(vertices2, -normal);
+    let (reference, incident, face) = if edge1.slant(normal) <= edge2.slant(normal) {
+        (edge1, edge2, normal)
     } else {
-        return Some([info1, info2]);
+        (edge2, edge1, -normal)
+    };
+
+    let along = (reference.b - reference.a).normalize_or_zero();
+    let mut points = [incident.a, incident.b];
+    let mut count = 0;
+    if let Some(clipped) = clip([incident.a, incident.b], along, along.dot(reference.a))
+        .and_then(|clipped| clip(clipped, -along, -along.dot(reference.b)))
+    {
+        let face = if along.perp().dot(face) < 0.0 {
+            -along.perp()
+        } else {
+            along.perp()
+        };
+        let offset = face.dot(reference.a);
+        for point in clipped {
+            if face.dot(point) <= offset {
+                points[count] = point;
+                count += 1;
+            }
+        }
+    }
+    if count == 0 {
+        points[0] = incident.deepest;
+        count = 1;
     }
+
+    Some(Manifold {
+        normal,
+        depth,
+        points,
+        count,
+        offset: Vec2::ZERO,
+    })
 }
 
-fn inside(vertice: &Vec2, object: &impl Shape) -> Option<MTV> {
-    let vertices = object.shape();
-    let mut surface_perps = vec![];
+#[derive(Clone, Copy)]
+struct Edge {
+    deepest: Vec2,
+    a: Vec2,
+    b: Vec2,
+}
 
-    for it in 0..vertices.len() {
-        let b = vertices[(it + 1) % vertices.len()];
-        let a = vertices[it % vertices.len()];
-        let normal = b - a;
-        let orthogonal = rotate(&normal, TAU / 4.0).normalize();
+impl Edge {
+    // 0.0 when the edge is square to `normal`
+    fn slant(&self, normal: Vec2) -> f32 {
+        (self.b - self.a).normalize_or_zero().dot(normal).abs()
+    }
+}
 
-        surface_perps.push(orthogonal);
+// Normal of the edge starting at vertice `it`, pointing away from `center`.
+fn outward(vertices: &[Vec2], it: usize, center: Vec2) -> Vec2 {
+    let a = vertices[it];
+    let b = vertices[(it + 1) % vertices.len()];
+    let normal = (b - a).perp().normalize_or_zero();
+    if normal.dot(a - center) < 0.0 {
+        -normal
+    } else {
+        normal
     }
+}
 
-    let mut min_overlap_magnitude = f32::MAX;
-    let mut min_overlap_direction = surface_perps[0];
-    for axis in surface_perps {
-        let projections: Vec<f32> = vertices.iter().map(|vertice| vertice.dot(axis)).collect();
-        let min_proj = (&projections)
-            .into_iter()
-            .min_by(|a, b| a.total_cmp(b))
-            .unwrap();
-        let max_proj = (&projections)
-            .into_iter()
-            .max_by(|a, b| a.total_cmp(b))
-            .unwrap();
-        let vertice_proj = vertice.dot(axis);
+fn project(vertices: &[Vec2], axis: Vec2) -> (f32, f32) {
+    vertices
+        .iter()
+        .map(|vertice| vertice.dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), proj| {
+            (min.min(proj), max.max(proj))
+        })
+}
 
-        if vertice_proj < *min_proj {
-            return None;
+// Of the two edges at the vertice furthest along `direction`, the one most
+// square to it.
+fn facing_edge(vertices: &[Vec2], direction: Vec2) -> Edge {
+    let mut furthest = 0;
+    for it in 1..vertices.len() {
+        if vertices[it].dot(direction) > vertices[furthest].dot(direction) {
+            furthest = it;
         }
+    }
+    let deepest = vertices[furthest];
+    let prev = vertices[(furthest + vertices.len() - 1) % vertices.len()];
+    let next = vertices[(furthest + 1) % vertices.len()];
+    let before = Edge {
+        deepest,
+        a: prev,
+        b: deepest,
+    };
+    let after = Edge {
+        deepest,
+        a: deepest,
+        b: next,
+    };
+    if before.slant(direction) <= after.slant(direction) {
+        before
+    } else {
+        after
+    }
+}
 
-        if vertice_proj > *max_proj {
-            return None;
+// The part of the segment where `direction.dot(point) >= offset`.
+fn clip(segment: [Vec2; 2], direction: Vec2, offset: f32) -> Option<[Vec2; 2]> {
+    let [a, b] = segment;
+    let (side_a, side_b) = (direction.dot(a) - offset, direction.dot(b) - offset);
+    match (side_a >= 0.0, side_b >= 0.0) {
+        (true, true) => Some(segment),
+        (false, false) => None,
+        (keep_a, _) => {
+            let crossing = a + (b - a) * side_a / (side_a - side_b);
+            Some(if keep_a { [a, crossing] } else { [crossing, b] })
         }
+    }
+}
 
-        let overlap = (*max_proj - vertice_proj).min(vertice_proj - *min_proj);
+// Compatibility wrapper reporting the vertices of each object found inside the
+// other. Shapes that only cross edge to edge collide with both lists empty.
+// Outlines may be concave, so vertices are tested against their convex pieces.
+#[allow(dead_code)]
+pub fn collision(
+    object1: &impl Shape,
+    object2: &impl Shape,
+) -> Option<[Vec<VerticeCollisionInfo>; 2]> {
+    deepest(&object1.parts(), &object2.parts())?;
 
+    let vertices1 = object1.shape();
+    let vertices2 = object2.shape();
+    let inside_of = |vertices: &[Vec2], other: &[Vec2]| {
+        let pieces = decompose(other);
+        vertices
+            .iter()
+            .enumerate()
+            .filter_map(|(vertice_index, vertice)| {
+                pieces
+                    .iter()
+                    .find_map(|piece| inside(vertice, piece))
+                    .map(|mtv| VerticeCollisionInfo { vertice_index, mtv })
+            })
+            .collect()
+    };
+    Some([
+        inside_of(&vertices1, &vertices2),
+        inside_of(&vertices2, &vertices1),
+    ])
+}
+
+// While the world rewinds, objects sharing a timeline have already met in the
+// recorded past, so only pairs across timelines are tested.
+pub fn collision_across_time(
+    object1: &(impl Shape + TimeImmune),
+    travel1: Vec2,
+    object2: &(impl Shape + TimeImmune),
+    travel2: Vec2,
+    bounds: Bounds,
+    rewinding: bool,
+) -> Option<Manifold> {
+    if rewinding && object1.time_immune() == object2.time_immune() {
+        return None;
+    }
+    sweep(object1, travel1, object2, travel2, bounds).map(|(_, manifold)| manifold)
+}
+
+// [CCD]
+// Bodies may cover more than their own size in one tick, a bullet easily
+// jumps over a fragment or the level-3 wall. Both objects are walked back
+// along what they travelled during the tick and tested at substeps no further
+// apart than this, a bit less than the 5px height of a bullet.
+const MAX_STEP: f32 = 4.0;
+
+// First contact between two objects that moved by `travel1` and `travel2` to
+// get where they are now, with the fraction of the tick at which it happened.
+// Contact points are where object1 was at that moment.
+pub fn sweep(
+    object1: &impl Shape,
+    travel1: Vec2,
+    object2: &impl Shape,
+    travel2: Vec2,
+    bounds: Bounds,
+) -> Option<(f32, Manifold)> {
+    let relative = travel1 - travel2;
+    let mut parts1 = object1.parts();
+    let mut parts2 = object2.parts();
+    let offset = nearest_image(&parts1, &mut parts2, bounds);
+    let steps = (relative.length() / MAX_STEP).ceil().max(1.0) as usize;
+    if steps == 1 {
+        return deepest(&parts1, &parts2).map(|manifold| (1.0, Manifold { offset, ..manifold }));
+    }
+
+    // back to where object1 started, then forward one substep at a time
+    let substep = relative / steps as f32;
+    parts1.iter_mut().for_each(|part| part.translate(-relative));
+    for step in 1..=steps {
+        let time = step as f32 / steps as f32;
+        parts1.iter_mut().for_each(|part| part.translate(substep));
+        if let Some(manifold) = deepest(&parts1, &parts2) {
+            return Some((time, Manifold { offset, ..manifold }));
+        }
+    }
+    None
+}
+
+fn inside(vertice: &Vec2, vertices: &[Vec2]) -> Option<MTV> {
+    let center = center(vertices);
+    let mut min_overlap_magnitude = f32::MAX;
+    let mut min_overlap_direction = Vec2::ZERO;
+    for it in 0..vertices.len() {
+        let axis = outward(vertices, it, center);
+        let (min_proj, max_proj) = project(vertices, axis);
+        let vertice_proj = vertice.dot(axis);
+
+        if vertice_proj < min_proj || vertice_proj > max_proj {
+            return None;
+        }
+
+        let overlap = (max_proj - vertice_proj).min(vertice_proj - min_proj);
         if overlap < min_overlap_magnitude {
             min_overlap_magnitude = overlap;
             min_overlap_direction = axis;
@@ -76,25 +483,518 @@ fn inside(vertice: &Vec2, object: &impl Shape) -> Option<MTV> {
     });
 }
 
+#[allow(dead_code)]
 struct MTV {
     min_overlap_magnitude: f32,
     min_overlap_direction: Vec2,
 }
 
+#[allow(dead_code)]
 pub struct VerticeCollisionInfo {
     vertice_index: usize,
     mtv: MTV,
 }
 
-fn rotate(vector: &Vec2, theta: f32) -> Vec2 {
-    Mat2 {
-        x_axis: Vec2 {
-            x: theta.cos(),
-            y: -1.0 * theta.sin(),
-        },
-        y_axis: Vec2 {
-            x: theta.sin(),
-            y: theta.cos(),
-        },
-    } * *vector
+// [RigidBodies]
+// Contact between two shapes, the middle of the manifold points,
+// `normal` points from object1 towards object2.
+pub struct Contact {
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub depth: f32,
+    // object2 met object1 this far from where its body is
+    pub offset: Vec2,
+}
+
+pub fn contact(object1: &impl Shape, object2: &impl Shape, bounds: Bounds) -> Option<Contact> {
+    let manifold = manifold(object1, object2, bounds)?;
+    let point = manifold.points().iter().copied().sum::<Vec2>() / manifold.count as f32;
+
+    Some(Contact {
+        point,
+        normal: manifold.normal,
+        depth: manifold.depth,
+        offset: manifold.offset,
+    })
+}
+
+// Only a share of the overlap is corrected per tick, so that deep overlaps
+// come apart smoothly instead of popping.
+const CORRECTION: f32 = 0.2;
+const SLOP: f32 = 0.5;
+
+// Pushes both bodies apart along the contact normal and exchanges linear and
+// angular momentum with an impulse at the contact point, plus a friction
+// impulse along the surface.
+pub fn resolve(body1: &mut Body, body2: &mut Body, contact: &Contact) {
+    let (inv_mass1, inv_mass2) = (1.0 / body1.mass, 1.0 / body2.mass);
+    let (inv_inertia1, inv_inertia2) = (1.0 / body1.inertia, 1.0 / body2.inertia);
+    let total_inv_mass = inv_mass1 + inv_mass2;
+    if total_inv_mass == 0.0 {
+        return;
+    }
+    let normal = contact.normal;
+
+    let correction = CORRECTION * (contact.depth - SLOP).max(0.0) / total_inv_mass * normal;
+    body1.lin_pos -= inv_mass1 * correction;
+    body2.lin_pos += inv_mass2 * correction;
+
+    let r1 = contact.point - body1.lin_pos;
+    let r2 = contact.point - (body2.lin_pos + contact.offset);
+    let relative =
+        body2.lin_vel + body2.ang_vel * r2.perp() - body1.lin_vel - body1.ang_vel * r1.perp();
+    let approach = relative.dot(normal);
+    if approach > 0.0 {
+        return;
+    }
+
+    let effective_inv_mass = |direction: Vec2| {
+        let r1_cross = r1.perp_dot(direction);
+        let r2_cross = r2.perp_dot(direction);
+        total_inv_mass + r1_cross * r1_cross * inv_inertia1 + r2_cross * r2_cross * inv_inertia2
+    };
+
+    let restitution = body1.restitution.min(body2.restitution);
+    let normal_impulse = -(1.0 + restitution) * approach / effective_inv_mass(normal);
+    body1.apply_impulse(-normal_impulse * normal, r1);
+    body2.apply_impulse(normal_impulse * normal, r2);
+
+    // Coulomb friction, never more than `friction` times the normal impulse
+    let tangent = relative - approach * normal;
+    if tangent.length_squared() > f32::EPSILON {
+        let tangent = tangent.normalize();
+        let friction = (body1.friction * body2.friction).sqrt();
+        let tangent_impulse = (-relative.dot(tangent) / effective_inv_mass(tangent))
+            .clamp(-friction * normal_impulse, friction * normal_impulse);
+        body1.apply_impulse(-tangent_impulse * tangent, r1);
+        body2.apply_impulse(tangent_impulse * tangent, r2);
+    }
+}
+
+// Mass and moment of inertia around `center` of a uniform polygon, either winding.
+pub fn mass_properties(vertices: &[Vec2], center: Vec2, density: f32) -> (f32, f32) {
+    let mut area = 0.0;
+    let mut moment = 0.0;
+    for it in 0..vertices.len() {
+        let a = vertices[it] - center;
+        let b = vertices[(it + 1) % vertices.len()] - center;
+        let cross = a.perp_dot(b);
+        area += cross / 2.0;
+        moment += cross * (a.dot(a) + a.dot(b) + b.dot(b)) / 12.0;
+    }
+    (density * area.abs(), density * moment.abs())
+}
+
+pub fn area(vertices: &[Vec2]) -> f32 {
+    signed_area(vertices).abs()
+}
+
+// Positive when the vertices turn from +x towards +y, which on screen (y down)
+// is clockwise.
+pub fn signed_area(vertices: &[Vec2]) -> f32 {
+    let mut area = 0.0;
+    for it in 0..vertices.len() {
+        area += vertices[it].perp_dot(vertices[(it + 1) % vertices.len()]) / 2.0;
+    }
+    area
+}
+
+// [ShapeWinding]
+// The narrowphase only handles convex polygons without self-intersections.
+// Every shape is expected positively wound (see `signed_area`), `normalize`
+// turns any point list into such a polygon.
+pub fn is_convex(vertices: &[Vec2]) -> bool {
+    if vertices.len() < 3 {
+        return false;
+    }
+    let mut turns = 0.0;
+    let mut angle = 0.0;
+    for it in 0..vertices.len() {
+        let a = vertices[it];
+        let b = vertices[(it + 1) % vertices.len()];
+        let c = vertices[(it + 2) % vertices.len()];
+        let (ab, bc) = (b - a, c - b);
+        let turn = ab.perp_dot(bc);
+        // collinear and repeated vertices, as left by clipping, are fine
+        if turn.abs() <= 1e-4 * ab.length() * bc.length() {
+            continue;
+        }
+        if turn * turns < 0.0 {
+            return false;
+        }
+        turns += turn;
+        angle += ab.angle_between(bc);
+    }
+    // a star turns one way at every corner but goes around more than once
+    turns != 0.0 && angle.abs() < TAU + 1e-2
+}
+
+pub fn is_normalized(vertices: &[Vec2]) -> bool {
+    is_convex(vertices) && signed_area(vertices) > 0.0
+}
+
+// Convex polygons only get their winding fixed, anything else becomes its hull.
+pub fn normalize(mut vertices: Vec<Vec2>) -> Vec<Vec2> {
+    if !is_convex(&vertices) {
+        return convex_hull(&vertices);
+    }
+    if signed_area(&vertices) < 0.0 {
+        vertices.reverse();
+    }
+    vertices
+}
+
+// Positive winding, without turning a concave outline into its hull.
+pub fn wind(mut vertices: Vec<Vec2>) -> Vec<Vec2> {
+    if signed_area(&vertices) < 0.0 {
+        vertices.reverse();
+    }
+    vertices
+}
+
+// [ConvexParts]
+// Concave outlines are ear clipped into triangles, then neighbouring pieces
+// are merged for as long as the result stays convex (Hertel-Mehlhorn). That
+// leaves only a few parts for the narrowphase to go through.
+pub fn decompose(outline: &[Vec2]) -> Vec<Vec<Vec2>> {
+    let outline = wind(outline.to_vec());
+    if is_convex(&outline) {
+        return vec![outline];
+    }
+
+    let mut pieces = triangulate(&outline);
+    'merging: loop {
+        for it in 0..pieces.len() {
+            for other in it + 1..pieces.len() {
+                let Some(merged) = merge(&pieces[it], &pieces[other]) else {
+                    continue;
+                };
+                let vertices: Vec<Vec2> = merged.iter().map(|index| outline[*index]).collect();
+                if is_convex(&vertices) {
+                    pieces[it] = merged;
+                    pieces.swap_remove(other);
+                    continue 'merging;
+                }
+            }
+        }
+        break;
+    }
+
+    pieces
+        .into_iter()
+        .map(|piece| {
+            piece
+                .iter()
+                .map(|index| outline[*index])
+                .collect::<Vec<Vec2>>()
+        })
+        .filter(|part| area(part) > 1e-3)
+        .map(normalize)
+        .collect()
+}
+
+// Indices of the triangles of a positively wound simple polygon. Whatever is
+// left when no ear can be found (collinear leftovers) comes back as one piece.
+fn triangulate(vertices: &[Vec2]) -> Vec<Vec<usize>> {
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corners = |it: usize| {
+            (
+                remaining[(it + count - 1) % count],
+                remaining[it],
+                remaining[(it + 1) % count],
+            )
+        };
+        let ear = (0..count).find(|it| {
+            let (a, b, c) = corners(*it);
+            let (pa, pb, pc) = (vertices[a], vertices[b], vertices[c]);
+            (pb - pa).perp_dot(pc - pb) > 0.0
+                && !remaining.iter().any(|other| {
+                    ![a, b, c].contains(other) && in_triangle(vertices[*other], pa, pb, pc)
+                })
+        });
+        let Some(ear) = ear else {
+            break;
+        };
+        let (a, b, c) = corners(ear);
+        triangles.push(vec![a, b, c]);
+        remaining.remove(ear);
+    }
+    if remaining.len() >= 3 {
+        triangles.push(remaining);
+    }
+    triangles
+}
+
+fn in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(point - a) >= 0.0
+        && (c - b).perp_dot(point - b) >= 0.0
+        && (a - c).perp_dot(point - c) >= 0.0
+}
+
+// Two pieces sharing an edge become one, walking the first from the shared
+// edge around and then the rest of the second.
+fn merge(piece1: &[usize], piece2: &[usize]) -> Option<Vec<usize>> {
+    let (count1, count2) = (piece1.len(), piece2.len());
+    for it in 0..count1 {
+        let (a, b) = (piece1[it], piece1[(it + 1) % count1]);
+        let Some(other) =
+            (0..count2).find(|other| piece2[*other] == b && piece2[(other + 1) % count2] == a)
+        else {
+            continue;
+        };
+        let mut merged: Vec<usize> = (0..count1).map(|k| piece1[(it + 1 + k) % count1]).collect();
+        merged.extend((2..count2).map(|k| piece2[(other + k) % count2]));
+        return Some(merged);
+    }
+    None
+}
+
+// Andrew's monotone chain, positively wound and without collinear points.
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(2 * sorted.len());
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if (b - a).perp_dot(point - b) > 0.0 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+// Centre of mass of a uniform polygon, either winding.
+pub fn centroid(vertices: &[Vec2]) -> Vec2 {
+    let origin = center(vertices);
+    let mut area = 0.0;
+    let mut moment = Vec2::ZERO;
+    for it in 0..vertices.len() {
+        let a = vertices[it] - origin;
+        let b = vertices[(it + 1) % vertices.len()] - origin;
+        let cross = a.perp_dot(b);
+        area += cross / 2.0;
+        moment += cross * (a + b) / 6.0;
+    }
+    if area == 0.0 {
+        return origin;
+    }
+    origin + moment / area
+}
+
+fn center(vertices: &[Vec2]) -> Vec2 {
+    vertices.iter().copied().sum::<Vec2>() / vertices.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Asteroid, Bullet, Rect, Rng};
+
+    // Even-odd ray casting, the reference for what a polygon covers. Knows
+    // nothing about winding or convexity.
+    fn covers(vertices: &[Vec2], point: Vec2) -> bool {
+        let mut inside = false;
+        for it in 0..vertices.len() {
+            let a = vertices[it];
+            let b = vertices[(it + 1) % vertices.len()];
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    fn random_point(rng: &mut Rng, extent: f32) -> Vec2 {
+        Vec2::new(rng.range(-extent, extent), rng.range(-extent, extent))
+    }
+
+    // Corners on a circle at increasing angles, from a random first corner
+    // and in a random direction.
+    fn random_convex(rng: &mut Rng) -> Vec<Vec2> {
+        let corners = 3 + (rng.next_u64() % 8) as usize;
+        let mut angles: Vec<f32> = (0..corners).map(|_| rng.range(0.0, TAU)).collect();
+        angles.sort_by(f32::total_cmp);
+        let radius = rng.range(5.0, 50.0);
+        let mut vertices: Vec<Vec2> = angles
+            .iter()
+            .map(|angle| radius * Vec2::from_angle(*angle))
+            .collect();
+        vertices.rotate_left(rng.next_u64() as usize % corners);
+        if rng.next_f32() < 0.5 {
+            vertices.reverse();
+        }
+        vertices
+    }
+
+    // Top left, top right, bottom left, bottom right: the order `Rect` and
+    // `Bullet` used to hand out, crossing itself in the middle.
+    fn zig_zag(center: Vec2, width: f32, height: f32, angle: f32) -> Vec<Vec2> {
+        [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+            .iter()
+            .map(|(x, y)| {
+                center + Vec2::from_angle(angle).rotate(Vec2::new(x * width, y * height) / 2.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn normalize_keeps_what_a_convex_polygon_covers() {
+        let mut rng = Rng::new(11);
+        for _ in 0..200 {
+            let vertices = random_convex(&mut rng);
+            assert!(is_convex(&vertices));
+            let normalized = normalize(vertices.clone());
+            assert!(is_normalized(&normalized));
+            for _ in 0..50 {
+                let point = random_point(&mut rng, 60.0);
+                assert_eq!(covers(&normalized, point), covers(&vertices, point));
+            }
+        }
+    }
+
+    #[test]
+    fn zig_zag_rects_and_bullets_normalize_to_their_box() {
+        let mut rng = Rng::new(12);
+        for (width, height) in [(20.0, 150.0), (10.0, 5.0)] {
+            for _ in 0..100 {
+                let center = random_point(&mut rng, 400.0);
+                let angle = rng.range(0.0, TAU);
+                let vertices = zig_zag(center, width, height, angle);
+                assert!(!is_convex(&vertices));
+                let normalized = normalize(vertices);
+                assert!(is_normalized(&normalized));
+                for _ in 0..50 {
+                    let point = center + random_point(&mut rng, width.max(height));
+                    let local = Vec2::from_angle(-angle).rotate(point - center);
+                    let in_box = local.x.abs() < width / 2.0 && local.y.abs() < height / 2.0;
+                    assert_eq!(covers(&normalized, point), in_box);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rect_and_bullet_shapes_are_normalized() {
+        let mut rng = Rng::new(13);
+        for _ in 0..100 {
+            let rect = Rect::new(
+                rng.range(0.0, 800.0),
+                rng.range(0.0, 600.0),
+                rng.range(1.0, 200.0),
+                rng.range(1.0, 200.0),
+            );
+            assert!(is_normalized(&rect.shape()));
+            let bullet = Bullet {
+                body: Body {
+                    lin_pos: random_point(&mut rng, 400.0),
+                    ang_pos: rng.range(0.0, TAU),
+                    ..Default::default()
+                },
+                time_immune: false,
+            };
+            assert!(is_normalized(&bullet.shape()));
+        }
+    }
+
+    // A point lies in the hull of a cloud exactly when it lies in a triangle
+    // of three points of the cloud.
+    #[test]
+    fn convex_hull_covers_every_triangle_of_the_cloud() {
+        let mut rng = Rng::new(14);
+        for _ in 0..100 {
+            let cloud: Vec<Vec2> = (0..3 + rng.next_u64() % 6)
+                .map(|_| random_point(&mut rng, 50.0))
+                .collect();
+            let hull = convex_hull(&cloud);
+            assert!(is_normalized(&hull));
+            assert!(hull.iter().all(|vertice| cloud.contains(vertice)));
+            for _ in 0..50 {
+                let point = random_point(&mut rng, 60.0);
+                let mut in_triangle = false;
+                for a in 0..cloud.len() {
+                    for b in a + 1..cloud.len() {
+                        for c in b + 1..cloud.len() {
+                            in_triangle |= covers(&[cloud[a], cloud[b], cloud[c]], point);
+                        }
+                    }
+                }
+                assert_eq!(covers(&hull, point), in_triangle);
+            }
+        }
+    }
+
+    // Whatever `is_convex` lets through must cover the same points as its hull,
+    // the narrowphase relies on it. Shuffled corners are mostly not convex.
+    #[test]
+    fn is_convex_agrees_with_the_hull() {
+        let mut rng = Rng::new(15);
+        let (mut convex, mut not_convex) = (0, 0);
+        for _ in 0..500 {
+            let mut vertices = random_convex(&mut rng);
+            for it in (1..vertices.len()).rev() {
+                vertices.swap(it, rng.next_u64() as usize % (it + 1));
+            }
+            if !is_convex(&vertices) {
+                not_convex += 1;
+                continue;
+            }
+            convex += 1;
+            let hull = convex_hull(&vertices);
+            for _ in 0..50 {
+                let point = random_point(&mut rng, 60.0);
+                assert_eq!(covers(&vertices, point), covers(&hull, point));
+            }
+        }
+        assert!(convex > 0 && not_convex > 0);
+    }
+
+    #[test]
+    fn collision_handles_concave_asteroids() {
+        let mut rng = Rng::new(16);
+        let mut concave = 0;
+        for _ in 0..50 {
+            let asteroid = Asteroid::jagged(
+                Body {
+                    lin_pos: Vec2::new(400.0, 300.0),
+                    ..Default::default()
+                },
+                40.0,
+                &mut rng,
+                false,
+            );
+            if !is_convex(&asteroid.shape()) {
+                concave += 1;
+            }
+            let bullet = |x: f32| Bullet {
+                body: Body {
+                    lin_pos: Vec2::new(x, 300.0),
+                    ang_pos: 0.0,
+                    ..Default::default()
+                },
+                time_immune: false,
+            };
+            let [inside_asteroid, _] = collision(&bullet(400.0), &asteroid).unwrap();
+            assert_eq!(inside_asteroid.len(), 4);
+            assert!(collision(&bullet(300.0), &asteroid).is_none());
+        }
+        assert!(concave > 0);
+    }
 }
@@ -0,0 +1,103 @@
+use crate::{draw_circle_lines, draw_line, Color, Texture2D, Vec2, PINK, SKYBLUE};
+use crate::{Body, Draw, Update};
+
+#[derive(Clone)]
+pub enum Region {
+    Circle { center: Vec2, radius: f32 },
+    Polygon(Vec<Vec2>),
+}
+
+impl Region {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Region::Circle { center, radius } => center.distance(point) <= *radius,
+            Region::Polygon(vertices) => {
+                // crossing number, so designers may also draw concave zones
+                let mut inside = false;
+                for it in 0..vertices.len() {
+                    let a = vertices[it];
+                    let b = vertices[(it + 1) % vertices.len()];
+                    if (a.y > point.y) != (b.y > point.y)
+                        && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                    {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+}
+
+// [TimeDilation]
+// Bodies inside a field integrate with `dt * time_scale`, below 1.0 slows
+// them down and above 1.0 speeds them up.
+#[derive(Clone)]
+pub struct TimeField {
+    pub region: Region,
+    pub time_scale: f32,
+    pub lifetime: Option<f32>,
+}
+
+impl TimeField {
+    // The field the player leaves behind when using a charge.
+    pub fn dropped_at(center: Vec2) -> TimeField {
+        TimeField {
+            region: Region::Circle {
+                center,
+                radius: 120.0,
+            },
+            time_scale: 0.25,
+            lifetime: Some(5.0),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        matches!(self.lifetime, Some(lifetime) if lifetime <= 0.0)
+    }
+}
+
+impl Update for TimeField {
+    fn update(&mut self, dt: f32) {
+        if let Some(lifetime) = &mut self.lifetime {
+            *lifetime -= dt;
+        }
+    }
+}
+
+impl Draw for TimeField {
+    fn draw(&self, _spritesheet: Option<&Texture2D>) {
+        let base = if self.time_scale < 1.0 { SKYBLUE } else { PINK };
+        let alpha = match self.lifetime {
+            Some(lifetime) => 0.6 * lifetime.clamp(0.0, 1.0),
+            None => 0.6,
+        };
+        let color = Color::new(base.r, base.g, base.b, alpha);
+        match &self.region {
+            Region::Circle { center, radius } => {
+                draw_circle_lines(center.x, center.y, *radius, 2.0, color)
+            }
+            Region::Polygon(vertices) => {
+                for it in 0..vertices.len() {
+                    let a = vertices[it];
+                    let b = vertices[(it + 1) % vertices.len()];
+                    draw_line(a.x, a.y, b.x, b.y, 2.0, color);
+                }
+            }
+        }
+    }
+}
+
+pub fn time_scale_at(time_fields: &[TimeField], point: Vec2) -> f32 {
+    time_fields
+        .iter()
+        .filter(|time_field| time_field.region.contains(point))
+        .map(|time_field| time_field.time_scale)
+        .product()
+}
+
+// Bodies ease into the time scale around them instead of snapping at the border.
+pub fn dilate(body: &mut Body, time_fields: &[TimeField], dt: f32) {
+    let target = time_scale_at(time_fields, body.lin_pos);
+    body.time_scale += (target - body.time_scale) * (1.0 - (-5.0 * dt).exp());
+}
@@ -0,0 +1,205 @@
+use crate::{area, centroid, mass_properties};
+use crate::{Asteroid, Body, Bullet, Mat2, Shape, Vec2};
+
+// Asteroids smaller than this crumble into smoke instead of splitting.
+const DUST_AREA: f32 = 1500.0;
+// A cut that would leave a sliver smaller than this only pushes the asteroid.
+const MIN_PIECE_AREA: f32 = 300.0;
+// How much of the bullet's momentum pushes the two pieces apart.
+const SPLIT: f32 = 0.5;
+
+// [Fracture]
+// The bullet is absorbed into the asteroid first: mass, linear momentum and
+// angular momentum of both are merged into one rigid body. That body is then
+// cut along the bullet's line of flight and every piece keeps moving the way
+// its part of the body was moving, so the pieces add up to the parent plus the
+// bullet. The pieces are finally pushed apart with equal and opposite impulses
+// at the same point, which changes neither momentum.
+//
+// Returns what is left of the asteroid, nothing when it turned to dust.
+pub fn fracture(asteroid: &Asteroid, bullet: &Bullet) -> Vec<Asteroid> {
+    let vertices = asteroid.shape();
+    let parent_area = area(&vertices);
+    if parent_area < DUST_AREA {
+        return vec![];
+    }
+
+    let center = asteroid.body.lin_pos;
+    let mass = asteroid.body.mass + bullet.body.mass;
+    let density = mass / parent_area;
+    let lin_vel = (asteroid.body.mass * asteroid.body.lin_vel
+        + bullet.body.mass * bullet.body.lin_vel)
+        / mass;
+    let inertia = asteroid.body.inertia * mass / asteroid.body.mass;
+    let ang_vel = (asteroid.body.inertia * asteroid.body.ang_vel
+        + (bullet.body.lin_pos - center).perp_dot(bullet.body.mass * bullet.body.lin_vel))
+        / inertia;
+
+    let through = bullet.body.lin_pos;
+    let normal = bullet.body.lin_vel.normalize_or_zero().perp();
+    let halves = [
+        clip(&vertices, through, normal),
+        clip(&vertices, through, -normal),
+    ];
+    let pieces = if halves
+        .iter()
+        .any(|half| half.len() < 3 || area(half) < MIN_PIECE_AREA)
+    {
+        vec![vertices]
+    } else {
+        halves.to_vec()
+    };
+
+    let unrotate = Mat2::from_angle(-asteroid.body.ang_pos);
+    let mut fragments: Vec<Asteroid> = pieces
+        .into_iter()
+        .map(|piece| {
+            let piece_center = centroid(&piece);
+            let (piece_mass, piece_inertia) = mass_properties(&piece, piece_center, density);
+            let outline = piece
+                .iter()
+                .map(|vertice| unrotate * (*vertice - piece_center))
+                .collect();
+            Asteroid::from_outline(
+                Body {
+                    lin_pos: piece_center,
+                    lin_vel: lin_vel + ang_vel * (piece_center - center).perp(),
+                    ang_pos: asteroid.body.ang_pos,
+                    ang_vel,
+                    mass: piece_mass,
+                    inertia: piece_inertia,
+                    restitution: asteroid.body.restitution,
+                    friction: asteroid.body.friction,
+                    time_scale: asteroid.body.time_scale,
+                    integrator: asteroid.body.integrator,
+                    ..Default::default()
+                },
+                outline,
+                asteroid.time_immune,
+            )
+        })
+        .collect();
+
+    if let [first, second] = &mut fragments[..] {
+        let impulse = SPLIT * bullet.body.mass * bullet.body.lin_vel.length() * normal;
+        first
+            .body
+            .apply_impulse(impulse, through - first.body.lin_pos);
+        second
+            .body
+            .apply_impulse(-impulse, through - second.body.lin_pos);
+    }
+    fragments
+}
+
+// The part of a polygon on the side of the line through `point` that `normal`
+// points to. Cutting a concave outline may join its pieces along the line,
+// `Asteroid::from_outline` drops those zero-width joints from the parts.
+fn clip(vertices: &[Vec2], point: Vec2, normal: Vec2) -> Vec<Vec2> {
+    let mut clipped = vec![];
+    for it in 0..vertices.len() {
+        let a = vertices[it];
+        let b = vertices[(it + 1) % vertices.len()];
+        let side_a = (a - point).dot(normal);
+        let side_b = (b - point).dot(normal);
+        if side_a >= 0.0 {
+            clipped.push(a);
+        }
+        if (side_a >= 0.0) != (side_b >= 0.0) {
+            clipped.push(a + (b - a) * side_a / (side_a - side_b));
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rng, TAU};
+
+    // Mass, centre of mass, linear momentum and angular momentum around the
+    // origin of a group of bodies.
+    fn totals(bodies: &[&Body]) -> (f32, Vec2, Vec2, f32) {
+        let mass: f32 = bodies.iter().map(|body| body.mass).sum();
+        let centroid = bodies
+            .iter()
+            .map(|body| body.mass * body.lin_pos)
+            .sum::<Vec2>()
+            / mass;
+        let momentum = bodies.iter().map(|body| body.mass * body.lin_vel).sum();
+        let angular = bodies
+            .iter()
+            .map(|body| {
+                body.inertia * body.ang_vel + body.lin_pos.perp_dot(body.mass * body.lin_vel)
+            })
+            .sum();
+        (mass, centroid, momentum, angular)
+    }
+
+    fn assert_close(actual: f32, expected: f32, scale: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-4 * scale,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn fragments_add_up_to_the_asteroid_and_the_bullet() {
+        let mut rng = Rng::new(10);
+        let mut splits = 0;
+        for _ in 0..500 {
+            let asteroid = Asteroid::jagged(
+                Body {
+                    lin_pos: Vec2::new(rng.range(0.0, 800.0), rng.range(0.0, 600.0)),
+                    lin_vel: Vec2::new(rng.range(-100.0, 100.0), rng.range(-100.0, 100.0)),
+                    ang_pos: rng.range(0.0, TAU),
+                    ang_vel: rng.range(-3.0, 3.0),
+                    ..Default::default()
+                },
+                rng.range(15.0, 80.0),
+                &mut rng,
+                false,
+            );
+            let angle = rng.range(0.0, TAU);
+            let mut bullet = Bullet {
+                body: Body {
+                    lin_pos: asteroid.body.lin_pos
+                        + asteroid.size
+                            * rng.range(0.0, 0.9)
+                            * Vec2::from_angle(rng.range(0.0, TAU)),
+                    lin_vel: 1000.0 * Vec2::from_angle(angle),
+                    ang_pos: angle,
+                    ang_vel: 0.0,
+                    ..Default::default()
+                },
+                time_immune: false,
+            };
+            bullet.body.mass = 10.0 * 5.0;
+            bullet.body.inertia = bullet.body.mass * (10.0 * 10.0 + 5.0 * 5.0) / 12.0;
+
+            let fragments = fracture(&asteroid, &bullet);
+            if fragments.is_empty() {
+                continue;
+            }
+            if fragments.len() == 2 {
+                splits += 1;
+            }
+            let (mass, centroid, momentum, angular) =
+                totals(&fragments.iter().map(|it| &it.body).collect::<Vec<_>>());
+            let (parent_mass, _, parent_momentum, parent_angular) =
+                totals(&[&asteroid.body, &bullet.body]);
+
+            assert_close(mass, parent_mass, parent_mass);
+            // the bullet is spread over the whole asteroid, which keeps its
+            // centre of mass where the asteroid had it
+            let extent = asteroid.body.lin_pos.length().max(asteroid.size);
+            assert_close(centroid.x, asteroid.body.lin_pos.x, extent);
+            assert_close(centroid.y, asteroid.body.lin_pos.y, extent);
+            let scale = parent_momentum.length().max(bullet.body.mass * 1000.0);
+            assert_close(momentum.x, parent_momentum.x, scale);
+            assert_close(momentum.y, parent_momentum.y, scale);
+            assert_close(angular, parent_angular, scale * extent);
+        }
+        assert!(splits > 100, "only {splits} splits");
+    }
+}
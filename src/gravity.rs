@@ -0,0 +1,83 @@
+use crate::{draw_circle, draw_circle_lines, Color, Texture2D, Vec2, DARKGRAY, VIOLET};
+use crate::{Body, Draw, Primitive, Shape, TimeImmune, TAU};
+
+// [Gravity]
+// A massive body pulling on everything with `strength / distance^2`. Wells
+// with a radius are planets, solid to ships, asteroids and bullets alike.
+#[derive(Clone)]
+pub struct Well {
+    pub center: Vec2,
+    // gravitational parameter G * M, in px^3 / s^2
+    pub strength: f32,
+    pub radius: f32,
+}
+
+// Below this distance the pull stops growing, so that passing through the
+// centre of a well without a planet does not fling bodies to infinity.
+const SOFTENING: f32 = 20.0;
+
+impl Well {
+    pub fn pull(&self, point: Vec2) -> Vec2 {
+        let towards = self.center - point;
+        let distance = towards.length().max(self.radius).max(SOFTENING);
+        self.strength * towards.normalize_or_zero() / (distance * distance)
+    }
+
+    // Planets are pinned in place by their infinite mass.
+    pub fn body(&self) -> Body {
+        Body {
+            lin_pos: self.center,
+            mass: f32::INFINITY,
+            inertia: f32::INFINITY,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.radius > 0.0
+    }
+}
+
+pub fn gravity_at(wells: &[Well], point: Vec2) -> Vec2 {
+    wells.iter().map(|well| well.pull(point)).sum()
+}
+
+impl Shape for Well {
+    fn shape(&self) -> Vec<Vec2> {
+        (0..16)
+            .map(|it| self.center + self.radius * Vec2::from_angle(it as f32 * TAU / 16.0))
+            .collect()
+    }
+
+    fn parts(&self) -> Vec<Primitive> {
+        vec![Primitive::Circle {
+            center: self.center,
+            radius: self.radius,
+        }]
+    }
+}
+
+impl TimeImmune for Well {
+    fn time_immune(&self) -> bool {
+        false
+    }
+}
+
+impl Draw for Well {
+    fn draw(&self, _spritesheet: Option<&Texture2D>) {
+        // rings where the pull drops to 400, 100 and 25 px/s^2
+        for (it, acceleration) in [400.0, 100.0, 25.0_f32].iter().enumerate() {
+            let radius = (self.strength / acceleration).sqrt();
+            if radius <= self.radius {
+                continue;
+            }
+            let alpha = 0.5 / (it + 1) as f32;
+            let color = Color::new(VIOLET.r, VIOLET.g, VIOLET.b, alpha);
+            draw_circle_lines(self.center.x, self.center.y, radius, 1.5, color);
+        }
+        if self.is_solid() {
+            draw_circle(self.center.x, self.center.y, self.radius, DARKGRAY);
+            draw_circle_lines(self.center.x, self.center.y, self.radius, 2.0, VIOLET);
+        }
+    }
+}
@@ -1,39 +1,75 @@
-pub mod level1;
-pub mod level2;
-pub mod level3;
+pub mod file;
+pub mod hub;
 pub mod menu;
 
-pub enum Level {
-    Lvl1,
-    Lvl2,
-    Lvl3,
-}
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use crate::Level;
+use file::LevelFile;
+use hub::{Hub, Progress};
+
+const DIRECTORY: &str = "assets/levels";
 
-struct LevelStruct {
-    level_initialised: bool,
+// [Levels]
+// Every playable level, in id order. Level files are picked up from
+// `assets/levels` on startup, levels written in Rust are registered by hand.
+pub struct Registry {
+    levels: Vec<Box<dyn Level>>,
 }
 
-impl Level {
-    fn level_init(&self) {
-        todo!();
+impl Registry {
+    pub fn new() -> Registry {
+        Registry { levels: vec![] }
     }
 
-    fn level_loop(&self) {
-        todo!();
+    // Replaces the level already registered under the same id.
+    pub fn register(&mut self, level: Box<dyn Level>) {
+        self.levels.retain(|other| other.id() != level.id());
+        self.levels.push(level);
+        self.levels.sort_by_key(|level| level.id());
+    }
+
+    pub fn get(&self, id: u8) -> Option<&dyn Level> {
+        self.levels
+            .iter()
+            .find(|level| level.id() == id)
+            .map(|level| level.as_ref())
+    }
+
+    // Where a new game starts.
+    pub fn first(&self) -> Option<&dyn Level> {
+        self.levels.first().map(|level| level.as_ref())
     }
 }
 
-/* impl Level for Level {
-    fn level_init(&self) {
-        match self {
-            GameLevel::Lvl1 => {
-                lvl1_init();
-            }
-            GameLevel::Lvl2 => {
-                lvl2_init();
-            }
+// All the levels of the game behind the hub. Broken level files are reported
+// and left out.
+pub fn registry(progress: Rc<RefCell<Progress>>) -> Registry {
+    let mut registry = Registry::new();
+    let entries = match fs::read_dir(DIRECTORY) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("{DIRECTORY}: {error}");
+            registry.register(Box::new(Hub {
+                levels: vec![],
+                progress,
+            }));
+            return registry;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "lvl") {
+            continue;
+        }
+        match LevelFile::load(&path) {
+            Ok(level) => registry.register(Box::new(level)),
+            Err(error) => eprintln!("{error}"),
         }
     }
-
-    fn level_loop(&self) {}
-} */
+    let levels = registry.levels.iter().map(|level| level.id()).collect();
+    registry.register(Box::new(Hub { levels, progress }));
+    registry
+}
@@ -0,0 +1,532 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{draw_circle_lines, draw_line, draw_text, draw_texture, Vec2, GOLD, GRAY, WHITE};
+use crate::{invalid, Clock, Draw, Level, Region, Rng, TimeField, Well};
+use crate::{Asteroid, Body, GameAssets, GameObjects, Integrator, Obstacle, Profile, Rect, Ship};
+
+// [LevelFiles]
+// Levels can be plain text files in `assets/levels`, named after their id, so
+// designers can add or tweak one without recompiling. One entry per line,
+// `#` starts a comment, points are written `x,y`:
+//
+//   title <text>                          shown in the corner, optional
+//   background none|dark|light
+//   integrator euler|verlet|rk4           for the ship and the asteroids
+//   shadow                                rewinding leaves a shadow ship
+//   bullets immune                        bullets keep flying while time rewinds
+//   ship x,y [angle=a]                    faces right unless turned
+//   asteroid x,y size=s [vel=x,y] [angle=a] [spin=w] [immune]
+//   obstacle x,y w,h [immune]             a wall, x,y is its top left corner
+//   field circle x,y radius scale=s
+//   field polygon x,y x,y x,y ... scale=s
+//   well x,y strength [radius=r]          a planet when it has a radius
+//   charges n                             time fields the player may drop
+//   lives n                               spare ships, 3 when left out
+//   physics [thrust=t] [turn=t] [brake=lin,ang] [clamp=lin,ang] [drag=lin,ang]
+//           [spin_drag=d]                 the ship's handling, see `Profile`. drag
+//                                         is the speeds where drag starts, spin_drag
+//                                         how hard spin past it slows, in rad/s^2
+//   par seconds                           clearing faster pays a bonus, 60 by default
+//   win cleared | win reach x,y radius
+//   next <id> | next menu
+
+pub struct LevelFile {
+    pub id: u8,
+    pub title: String,
+    pub background: Background,
+    pub integrator: Integrator,
+    pub profile: Profile,
+    pub casts_shadow: bool,
+    pub immune_bullets: bool,
+    pub ship: Body,
+    pub asteroids: Vec<Spawn>,
+    pub obstacles: Vec<Obstacle>,
+    pub time_fields: Vec<TimeField>,
+    pub wells: Vec<Well>,
+    pub field_charges: u8,
+    pub lives: u8,
+    pub par: f32,
+    pub goal: Goal,
+    // `None` goes back to the menu
+    pub next: Option<u8>,
+}
+
+// Asteroids are only described, their outline comes from the level seed.
+pub struct Spawn {
+    pub body: Body,
+    pub size: f32,
+    pub time_immune: bool,
+}
+
+pub enum Background {
+    None,
+    Dark,
+    Light,
+}
+
+pub enum Goal {
+    Cleared,
+    Reach(Region),
+}
+
+impl LevelFile {
+    // The id is the file name, `3.lvl` is level 3.
+    pub fn load(path: &Path) -> io::Result<LevelFile> {
+        let within =
+            |error: io::Error| io::Error::new(error.kind(), format!("{}: {error}", path.display()));
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+            .ok_or_else(|| within(invalid("level files are named after their id")))?;
+        let text = fs::read_to_string(path).map_err(within)?;
+        LevelFile::parse(id, &text).map_err(within)
+    }
+
+    pub fn parse(id: u8, text: &str) -> io::Result<LevelFile> {
+        let mut level = LevelFile {
+            id,
+            title: String::new(),
+            background: Background::None,
+            integrator: Integrator::default(),
+            profile: Profile::default(),
+            casts_shadow: false,
+            immune_bullets: false,
+            ship: Body::default(),
+            asteroids: vec![],
+            obstacles: vec![],
+            time_fields: vec![],
+            wells: vec![],
+            field_charges: 0,
+            lives: 3,
+            par: 60.0,
+            goal: Goal::Cleared,
+            next: None,
+        };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            entry(&mut level, keyword, rest.trim())
+                .map_err(|message| invalid(&format!("line {}: {message}", number + 1)))?;
+        }
+        Ok(level)
+    }
+}
+
+impl Level for LevelFile {
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn init(&self, game_objects: &mut GameObjects) {
+        let mut rng = Rng::new(game_objects.seed);
+        game_objects.asteroids = self
+            .asteroids
+            .iter()
+            .map(|spawn| {
+                Asteroid::jagged(spawn.body.clone(), spawn.size, &mut rng, spawn.time_immune)
+            })
+            .collect();
+        game_objects.bullets = vec![];
+        game_objects.smokes = vec![];
+        game_objects.debris = vec![];
+        game_objects.shadow = None;
+        game_objects.casts_shadow = self.casts_shadow;
+        game_objects.immune_bullets = self.immune_bullets;
+        game_objects.time_fields = self.time_fields.clone();
+        game_objects.field_charges = self.field_charges;
+        game_objects.wells = self.wells.clone();
+        game_objects.obstacles = self.obstacles.clone();
+        game_objects.ship = Ship::new(self.ship.clone());
+        game_objects.lives = self.lives;
+        game_objects.respawn_in = None;
+        game_objects.score.start_level(self.par);
+
+        // bullets and fragments take theirs from the body they come from
+        game_objects.ship.body.integrator = self.integrator;
+        game_objects
+            .asteroids
+            .iter_mut()
+            .for_each(|asteroid| asteroid.body.integrator = self.integrator);
+    }
+
+    fn draw(&self, clock: &Clock, game_objects: &GameObjects, game_assets: &GameAssets) {
+        match self.background {
+            Background::None => {}
+            Background::Dark => draw_texture(&game_assets.blob_dark, 0.0, 0.0, WHITE),
+            Background::Light => draw_texture(&game_assets.blob_light, 0.0, 0.0, WHITE),
+        }
+        if let Goal::Reach(region) = &self.goal {
+            draw_region(region);
+        }
+        draw_text(&self.title, 100.0, 100.0, 50.0, GRAY);
+        clock
+            .interpolated(game_objects)
+            .draw(Some(&game_assets.spritesheet));
+    }
+
+    fn is_won(&self, game_objects: &GameObjects) -> bool {
+        match &self.goal {
+            Goal::Cleared => game_objects.asteroids.is_empty(),
+            Goal::Reach(region) => region.contains(game_objects.ship.body.lin_pos),
+        }
+    }
+
+    fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    fn next(&self, _game_objects: &GameObjects) -> Option<u8> {
+        self.next
+    }
+}
+
+fn entry(level: &mut LevelFile, keyword: &str, rest: &str) -> Result<(), String> {
+    let mut fields = Fields {
+        tokens: rest.split_whitespace().collect(),
+    };
+    match keyword {
+        "title" => {
+            level.title = rest.to_string();
+            return Ok(());
+        }
+        "background" => {
+            level.background = match fields.next()? {
+                "none" => Background::None,
+                "dark" => Background::Dark,
+                "light" => Background::Light,
+                other => return Err(format!("unknown background `{other}`")),
+            }
+        }
+        "integrator" => {
+            level.integrator = match fields.next()? {
+                "euler" => Integrator::SemiImplicitEuler,
+                "verlet" => Integrator::VelocityVerlet,
+                "rk4" => Integrator::Rk4,
+                other => return Err(format!("unknown integrator `{other}`")),
+            }
+        }
+        "shadow" => level.casts_shadow = true,
+        "bullets" => {
+            level.immune_bullets = match fields.next()? {
+                "immune" => true,
+                other => return Err(format!("unknown bullet kind `{other}`")),
+            }
+        }
+        "physics" => {
+            let default = Profile::default();
+            let brake = Vec2::new(default.lin_brake, default.ang_brake);
+            let clamp = Vec2::new(default.max_lin_vel, default.max_ang_vel);
+            let drag = Vec2::new(default.lin_drag_above, default.ang_drag_above);
+            let brake = fields.point_or("brake", brake)?;
+            let clamp = fields.point_or("clamp", clamp)?;
+            let drag = fields.point_or("drag", drag)?;
+            level.profile = Profile {
+                thrust: fields.number_or("thrust", default.thrust)?,
+                turn: fields.number_or("turn", default.turn)?,
+                lin_brake: brake.x,
+                ang_brake: brake.y,
+                max_lin_vel: clamp.x,
+                max_ang_vel: clamp.y,
+                lin_drag_above: drag.x,
+                ang_drag_above: drag.y,
+                ang_drag: fields.number_or("spin_drag", default.ang_drag)?,
+            };
+        }
+        "ship" => {
+            level.ship = Body {
+                lin_pos: point(fields.next()?)?,
+                ang_pos: fields.number_or("angle", Body::default().ang_pos)?,
+                ..Default::default()
+            }
+        }
+        "asteroid" => {
+            let time_immune = fields.flag("immune");
+            let size = number(fields.named("size")?)?;
+            if size <= 0.0 {
+                return Err("an asteroid needs a positive size".to_string());
+            }
+            level.asteroids.push(Spawn {
+                body: Body {
+                    lin_pos: point(fields.next()?)?,
+                    lin_vel: fields.point_or("vel", Vec2::ZERO)?,
+                    ang_pos: fields.number_or("angle", 0.0)?,
+                    ang_vel: fields.number_or("spin", 0.0)?,
+                    ..Default::default()
+                },
+                size,
+                time_immune,
+            });
+        }
+        "obstacle" => {
+            let time_immune = fields.flag("immune");
+            let corner = point(fields.next()?)?;
+            let size = point(fields.next()?)?;
+            if size.x <= 0.0 || size.y <= 0.0 {
+                return Err("an obstacle needs a positive width and height".to_string());
+            }
+            level.obstacles.push(Obstacle {
+                rect: Rect::new(corner.x, corner.y, size.x, size.y),
+                time_immune,
+            });
+        }
+        "field" => {
+            let time_scale = number(fields.named("scale")?)?;
+            let region = match fields.next()? {
+                "circle" => Region::Circle {
+                    center: point(fields.next()?)?,
+                    radius: number(fields.next()?)?,
+                },
+                "polygon" => {
+                    let vertices = fields.rest().map(point).collect::<Result<Vec<_>, _>>()?;
+                    if vertices.len() < 3 {
+                        return Err("a polygon needs at least three points".to_string());
+                    }
+                    Region::Polygon(vertices)
+                }
+                other => return Err(format!("unknown field shape `{other}`")),
+            };
+            level.time_fields.push(TimeField {
+                region,
+                time_scale,
+                lifetime: None,
+            });
+        }
+        "well" => {
+            let radius = fields.number_or("radius", 0.0)?;
+            level.wells.push(Well {
+                center: point(fields.next()?)?,
+                strength: number(fields.next()?)?,
+                radius,
+            });
+        }
+        "charges" => {
+            level.field_charges = fields
+                .next()?
+                .parse()
+                .map_err(|_| "charges must be a whole number".to_string())?
+        }
+        "lives" => {
+            level.lives = fields
+                .next()?
+                .parse()
+                .map_err(|_| "lives must be a whole number".to_string())?
+        }
+        "par" => level.par = number(fields.next()?)?,
+        "win" => {
+            level.goal = match fields.next()? {
+                "cleared" => Goal::Cleared,
+                "reach" => Goal::Reach(Region::Circle {
+                    center: point(fields.next()?)?,
+                    radius: number(fields.next()?)?,
+                }),
+                other => return Err(format!("unknown win condition `{other}`")),
+            }
+        }
+        "next" => {
+            level.next = match fields.next()? {
+                "menu" => None,
+                id => Some(id.parse().map_err(|_| format!("unknown level `{id}`"))?),
+            }
+        }
+        other => return Err(format!("unknown entry `{other}`")),
+    }
+    fields.finish()
+}
+
+// The values after the keyword: plain ones in order, `name=value` ones and
+// bare flags in any order.
+struct Fields<'a> {
+    tokens: Vec<&'a str>,
+}
+
+impl<'a> Fields<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        let at = self
+            .tokens
+            .iter()
+            .position(|token| !token.contains('='))
+            .ok_or_else(|| "missing value".to_string())?;
+        Ok(self.tokens.remove(at))
+    }
+
+    fn rest(&mut self) -> impl Iterator<Item = &'a str> {
+        let (plain, named) = self.tokens.iter().partition(|token| !token.contains('='));
+        self.tokens = named;
+        plain.into_iter()
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        match self.tokens.iter().position(|token| *token == name) {
+            Some(at) => {
+                self.tokens.remove(at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn named(&mut self, name: &str) -> Result<&'a str, String> {
+        self.optional(name)
+            .ok_or_else(|| format!("missing `{name}=`"))
+    }
+
+    fn optional(&mut self, name: &str) -> Option<&'a str> {
+        let at = self
+            .tokens
+            .iter()
+            .position(|token| token.split_once('=').is_some_and(|(key, _)| key == name))?;
+        let (_, value) = self.tokens.remove(at).split_once('=')?;
+        Some(value)
+    }
+
+    fn number_or(&mut self, name: &str, default: f32) -> Result<f32, String> {
+        self.optional(name).map_or(Ok(default), number)
+    }
+
+    fn point_or(&mut self, name: &str, default: Vec2) -> Result<Vec2, String> {
+        self.optional(name).map_or(Ok(default), point)
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self.tokens.first() {
+            Some(token) => Err(format!("unexpected `{token}`")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn number(token: &str) -> Result<f32, String> {
+    token
+        .parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("`{token}` is not a number"))
+}
+
+fn point(token: &str) -> Result<Vec2, String> {
+    let (x, y) = token
+        .split_once(',')
+        .ok_or_else(|| format!("`{token}` is not an x,y point"))?;
+    Ok(Vec2::new(number(x)?, number(y)?))
+}
+
+fn draw_region(region: &Region) {
+    match region {
+        Region::Circle { center, radius } => {
+            draw_circle_lines(center.x, center.y, *radius, 2.0, GOLD)
+        }
+        Region::Polygon(vertices) => {
+            for it in 0..vertices.len() {
+                let a = vertices[it];
+                let b = vertices[(it + 1) % vertices.len()];
+                draw_line(a.x, a.y, b.x, b.y, 2.0, GOLD);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::tests::world;
+    use crate::{step, Input, TICK};
+
+    // Level `id` from `assets/levels`, started the way the game starts it.
+    fn start(id: u8, text: &str) -> (LevelFile, GameObjects) {
+        let level = LevelFile::parse(id, text).unwrap();
+        let mut game_objects = world();
+        level.init(&mut game_objects);
+        game_objects.profile_target = level.profile();
+        (level, game_objects)
+    }
+
+    fn run(game_objects: &mut GameObjects, input: Input, seconds: f32) {
+        for _ in 0..(seconds / TICK) as usize {
+            let bounds = game_objects.bounds;
+            step(game_objects, input, bounds, TICK);
+        }
+    }
+
+    // Sweeping the guns around catches the fragments too.
+    const SWEEP: Input = Input {
+        thrust: false,
+        left: true,
+        right: false,
+        fire: true,
+        drop_field: false,
+        rewind: false,
+    };
+
+    #[test]
+    fn level_1_is_cleared_by_sweeping_fire() {
+        let (level, mut game_objects) = start(1, include_str!("../../assets/levels/1.lvl"));
+        run(&mut game_objects, SWEEP, 10.0);
+        assert!(level.is_won(&game_objects));
+        assert!(!level.is_lost(&game_objects));
+    }
+
+    #[test]
+    fn level_1_wrecks_a_ship_flying_into_the_rock() {
+        let (_, mut game_objects) = start(1, include_str!("../../assets/levels/1.lvl"));
+        let input = Input {
+            thrust: true,
+            ..Default::default()
+        };
+        run(&mut game_objects, input, 1.0);
+        assert!(game_objects.respawn_in.is_some());
+        assert!(!game_objects.debris.is_empty());
+    }
+
+    #[test]
+    fn level_2_is_cleared_by_sweeping_fire() {
+        let (level, mut game_objects) = start(2, include_str!("../../assets/levels/2.lvl"));
+        run(&mut game_objects, SWEEP, 15.0);
+        assert!(level.is_won(&game_objects));
+        assert!(!level.is_lost(&game_objects));
+    }
+
+    #[test]
+    fn level_3_wall_stops_the_bullets() {
+        let (level, mut game_objects) = start(3, include_str!("../../assets/levels/3.lvl"));
+        let fire = Input {
+            fire: true,
+            ..Default::default()
+        };
+        let mut farthest: f32 = 0.0;
+        for _ in 0..(5.0 / TICK) as usize {
+            let bounds = game_objects.bounds;
+            step(&mut game_objects, fire, bounds, TICK);
+            for bullet in &game_objects.bullets {
+                assert!(bullet.body.lin_pos.x < 400.0);
+                farthest = farthest.max(bullet.body.lin_pos.x);
+            }
+        }
+        // they got to the wall, within a tick or two of 1000 px/s
+        assert!(farthest >= 400.0 - 2.0 * 1000.0 * TICK);
+        assert_eq!(game_objects.asteroids.len(), 1);
+        assert!(!level.is_won(&game_objects));
+
+        // a bullet only takes a fraction of a second to the wall
+        run(&mut game_objects, Input::default(), 0.5);
+        assert!(game_objects.bullets.is_empty());
+    }
+
+    #[test]
+    fn ship_without_an_angle_faces_right() {
+        let level = LevelFile::parse(1, "ship 200,500").unwrap();
+        assert_eq!(level.ship.ang_pos, Body::default().ang_pos);
+    }
+
+    #[test]
+    fn empty_asteroids_and_obstacles_are_rejected() {
+        assert!(LevelFile::parse(1, "asteroid 700,500 size=0").is_err());
+        assert!(LevelFile::parse(1, "obstacle 400,425 -20,150").is_err());
+        assert!(LevelFile::parse(1, "obstacle 400,425 20,0").is_err());
+    }
+}
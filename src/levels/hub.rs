@@ -0,0 +1,215 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::{draw_circle_lines, draw_text, draw_texture, draw_triangle_lines, measure_text};
+use crate::{invalid, read_or_empty};
+use crate::{Body, Bounds, Clock, Draw, GameAssets, GameObjects, Level, Region, Shape, Ship};
+use crate::{Vec2, DARKGRAY, GOLD, GRAY, LIGHTGRAY, SKYBLUE, TAU, WHITE};
+
+pub const HUB: u8 = 0;
+const PORTAL_RADIUS: f32 = 45.0;
+
+// [Hub]
+// Level 0, an empty space where a ghost of the ship flies into portals to
+// pick a level. Ghosts do not collide, there is nothing to collide with and no
+// lives to lose. A level's portal opens once the level before it is cleared.
+pub struct Hub {
+    // every other registered level, in id order
+    pub levels: Vec<u8>,
+    pub progress: Rc<RefCell<Progress>>,
+}
+
+impl Hub {
+    // Portals sit in a row across the middle of the screen.
+    fn portals(&self, bounds: Bounds) -> Vec<(u8, Region)> {
+        let count = self.levels.len() as f32;
+        self.levels
+            .iter()
+            .enumerate()
+            .map(|(it, id)| {
+                let center = Vec2::new(
+                    bounds.width * (it as f32 + 1.0) / (count + 1.0),
+                    bounds.height / 2.0,
+                );
+                (
+                    *id,
+                    Region::Circle {
+                        center,
+                        radius: PORTAL_RADIUS,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn is_open(&self, id: u8) -> bool {
+        self.levels.first() == Some(&id) || self.progress.borrow().is_reached(id)
+    }
+
+    // The open portal the ghost is in, if any.
+    fn entered(&self, game_objects: &GameObjects) -> Option<u8> {
+        self.portals(game_objects.bounds)
+            .into_iter()
+            .find(|(id, region)| {
+                self.is_open(*id) && region.contains(game_objects.ship.body.lin_pos)
+            })
+            .map(|(id, _)| id)
+    }
+}
+
+impl Level for Hub {
+    fn id(&self) -> u8 {
+        HUB
+    }
+
+    fn init(&self, game_objects: &mut GameObjects) {
+        let bounds = game_objects.bounds;
+        game_objects.asteroids = vec![];
+        game_objects.bullets = vec![];
+        game_objects.smokes = vec![];
+        game_objects.debris = vec![];
+        game_objects.obstacles = vec![];
+        game_objects.shadow = None;
+        game_objects.casts_shadow = false;
+        game_objects.immune_bullets = false;
+        game_objects.time_fields = vec![];
+        game_objects.field_charges = 0;
+        game_objects.wells = vec![];
+        game_objects.lives = 0;
+        game_objects.respawn_in = None;
+        game_objects.score.start_level(0.0);
+        game_objects.ship = Ship::new(Body {
+            lin_pos: Vec2::new(bounds.width / 2.0, bounds.height - 100.0),
+            ang_pos: TAU / 4.0,
+            ..Default::default()
+        });
+    }
+
+    fn draw(&self, clock: &Clock, game_objects: &GameObjects, game_assets: &GameAssets) {
+        draw_texture(&game_assets.blob_dark, 0.0, 0.0, WHITE);
+        draw_text("Fly into a level", 100.0, 100.0, 50.0, GRAY);
+
+        let progress = self.progress.borrow();
+        for (id, region) in self.portals(game_objects.bounds) {
+            let Region::Circle { center, radius } = region else {
+                continue;
+            };
+            let (color, caption) = if !self.is_open(id) {
+                (DARKGRAY, "locked".to_string())
+            } else {
+                match progress.best(id) {
+                    Some(best) => (GOLD, format!("best {best:.1} s")),
+                    None => (SKYBLUE, "open".to_string()),
+                }
+            };
+            draw_circle_lines(center.x, center.y, radius, 4.0, color);
+            let label = id.to_string();
+            let size = measure_text(&label, None, 40, 1.0);
+            draw_text(
+                &label,
+                center.x - size.width / 2.0,
+                center.y + size.height / 2.0,
+                40.0,
+                color,
+            );
+            let size = measure_text(&caption, None, 20, 1.0);
+            draw_text(
+                &caption,
+                center.x - size.width / 2.0,
+                center.y + radius + 25.0,
+                20.0,
+                LIGHTGRAY,
+            );
+        }
+
+        // the ghost, drawn like a shadow, and whatever it fires
+        let world = clock.interpolated(game_objects);
+        world
+            .bullets
+            .iter()
+            .for_each(|bullet| bullet.draw(Some(&game_assets.spritesheet)));
+        let vertices = world.ship.shape();
+        draw_triangle_lines(vertices[0], vertices[1], vertices[2], 3.0, GRAY);
+    }
+
+    fn is_won(&self, game_objects: &GameObjects) -> bool {
+        self.entered(game_objects).is_some()
+    }
+
+    fn next(&self, game_objects: &GameObjects) -> Option<u8> {
+        self.entered(game_objects)
+    }
+}
+
+// Which levels were reached and the fastest clear of each, kept across runs
+// as one `id seconds` line per level, `-` for not cleared yet.
+pub struct Progress {
+    pub levels: Vec<(u8, Option<f32>)>,
+}
+
+impl Progress {
+    // A missing file is a fresh start.
+    pub fn load(path: &Path) -> io::Result<Progress> {
+        let text = read_or_empty(path)?;
+        let mut levels = vec![];
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let entry = line.split_once(' ').and_then(|(id, best)| {
+                let best = match best.trim() {
+                    "-" => None,
+                    seconds => Some(seconds.parse().ok()?),
+                };
+                Some((id.parse().ok()?, best))
+            });
+            levels.push(entry.ok_or_else(|| invalid(&format!("`{line}` is not progress")))?);
+        }
+        Ok(Progress { levels })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text: String = self
+            .levels
+            .iter()
+            .map(|(id, best)| match best {
+                Some(best) => format!("{id} {best}\n"),
+                None => format!("{id} -\n"),
+            })
+            .collect();
+        fs::write(path, text)
+    }
+
+    pub fn is_reached(&self, id: u8) -> bool {
+        self.levels.iter().any(|(other, _)| *other == id)
+    }
+
+    pub fn best(&self, id: u8) -> Option<f32> {
+        self.levels
+            .iter()
+            .find(|(other, _)| *other == id)
+            .and_then(|(_, best)| *best)
+    }
+
+    pub fn reach(&mut self, id: u8) {
+        if !self.is_reached(id) {
+            self.levels.push((id, None));
+            self.levels.sort_by_key(|(id, _)| *id);
+        }
+    }
+
+    // Records clearing `level` and opens the way to the one after it.
+    pub fn record(&mut self, level: &dyn Level, game_objects: &GameObjects) {
+        let id = level.id();
+        let seconds = game_objects.score.elapsed;
+        self.reach(id);
+        for (other, best) in self.levels.iter_mut() {
+            if *other == id && best.is_none_or(|best| seconds < best) {
+                *best = Some(seconds);
+            }
+        }
+        if let Some(next) = level.next(game_objects) {
+            self.reach(next);
+        }
+    }
+}
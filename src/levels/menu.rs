@@ -1,10 +1,15 @@
-use crate::Draw;
-use crate::{draw_text, draw_texture, BLACK, WHITE};
-use crate::{GameAssets, GameObjects};
+use crate::{draw_text, draw_texture, BLACK, GRAY, ORANGE, WHITE};
+use crate::{Clock, Draw};
+use crate::{GameAssets, GameObjects, HighScores};
 
 pub fn init() {}
 
-pub fn run(_game_last_tick: &mut f32, game_assets: &GameAssets, game_objects: &mut GameObjects) {
+pub fn run(
+    _clock: &mut Clock,
+    game_assets: &GameAssets,
+    game_objects: &mut GameObjects,
+    high_scores: &HighScores,
+) {
     draw_texture(&game_assets.blob_light, 0.0, 0.0, WHITE);
     game_objects.ship.draw(Some(&game_assets.spritesheet));
     game_objects
@@ -26,4 +31,50 @@ pub fn run(_game_last_tick: &mut f32, game_assets: &GameAssets, game_objects: &m
         24.0,
         BLACK,
     );
+
+    draw_text("High scores", 50.0, 200.0, 32.0, BLACK);
+    for (it, (initials, points)) in high_scores.entries.iter().enumerate() {
+        let y = 235.0 + 28.0 * it as f32;
+        draw_text(
+            &format!("{:>2}. {initials:<3}", it + 1),
+            50.0,
+            y,
+            28.0,
+            BLACK,
+        );
+        draw_text(&points.to_string(), 170.0, y, 28.0, BLACK);
+    }
+}
+
+// The world as the last ship broke apart, frozen under the verdict.
+pub fn game_over(game_assets: &GameAssets, game_objects: &GameObjects) {
+    draw_texture(&game_assets.blob_dark, 0.0, 0.0, WHITE);
+    game_objects.draw(Some(&game_assets.spritesheet));
+    draw_text("Game over", 50.0, 100.0, 60.0, ORANGE);
+    draw_text(
+        "Press enter to go back to the menu.",
+        50.0,
+        130.0,
+        24.0,
+        GRAY,
+    );
+}
+
+pub fn initials(game_assets: &GameAssets, initials: &str, points: u32) {
+    draw_texture(&game_assets.blob_light, 0.0, 0.0, WHITE);
+    draw_text(
+        &format!("New high score: {points}"),
+        50.0,
+        100.0,
+        60.0,
+        BLACK,
+    );
+    draw_text(
+        "Type your initials and press enter.",
+        50.0,
+        130.0,
+        24.0,
+        BLACK,
+    );
+    draw_text(&format!("{initials:_<3}"), 50.0, 220.0, 80.0, ORANGE);
 }
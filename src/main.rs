@@ -1,19 +1,54 @@
+mod broadphase;
+mod clock;
 mod collisions;
+mod dilation;
+mod fracture;
+mod gravity;
 mod levels;
 mod prelude;
+mod profile;
+mod replay;
+mod rewind;
+mod rng;
+mod score;
+mod sim;
+mod storage;
 
-use collisions::collision;
-use levels::Level;
-use levels::{level1, level2, level3, menu};
-use prelude::{Asteroid, Body, Bullet, Ship, Smoke};
-use prelude::{Draw, Shape, Update};
+use broadphase::{candidates, overlapping, self_candidates, Aabb};
+use clock::{screen_bounds, Clock};
+use collisions::{
+    area, centroid, collision_across_time, contact, decompose, mass_properties, resolve, sweep,
+    wind,
+};
+use dilation::{dilate, Region, TimeField};
+use fracture::fracture;
+use gravity::{gravity_at, Well};
+use levels::hub::{Progress, HUB};
+use levels::{menu, registry, Registry};
+use prelude::{Advance, Draw, Field, Level, Primitive, Shape, TimeImmune, Update};
+use prelude::{
+    Asteroid, Body, Bullet, Debris, Input, Integrator, Obstacle, Shadow, Ship, Smoke, DENSITY,
+};
 use prelude::{GameAssets, GameMode, GameObjects};
+use profile::Profile;
+use replay::{Replay, Tape};
+use rewind::History;
+use rng::Rng;
+use score::{HighScores, Score};
+use sim::{step, Bounds, TICK};
+use storage::{invalid, read_or_empty};
 
 use macroquad::{
     audio::{load_sound, play_sound, PlaySoundParams},
     prelude::*,
 };
+use std::cell::RefCell;
 use std::f32::consts::{PI, TAU};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const HIGH_SCORES: &str = "highscores.txt";
+const PROGRESS: &str = "progress.txt";
 
 #[macroquad::main("Braideroids : Asteroids = Braid;")]
 async fn main() {
@@ -33,15 +68,64 @@ async fn main() {
     let bullets = Vec::<Bullet>::new();
     let asteroids = Vec::<Asteroid>::new();
     let smokes = Vec::<Smoke>::new();
+    let obstacles = Vec::<Obstacle>::new();
     let mut game_objects = GameObjects {
         ship,
         asteroids,
         bullets,
         smokes,
+        debris: vec![],
+        obstacles,
+        shadow: None,
+        casts_shadow: false,
+        immune_bullets: false,
+        time_fields: vec![],
+        field_charges: 0,
+        wells: vec![],
+        lives: 0,
+        respawn_in: None,
+        score: Score::default(),
+        profile: Profile::default(),
+        profile_target: Profile::default(),
+        bounds: screen_bounds(),
+        seed: 0,
     };
 
-    let mut game_last_tick = get_time() as f32;
-    let mut game_mode = GameMode::Play(Level::Lvl3);
+    let mut history = History::new(64 * 1024 * 1024);
+    let mut clock = Clock::new(Tape::Recording(Replay::new(
+        0,
+        0,
+        screen_bounds(),
+        Profile::default(),
+    )));
+    let progress = Progress::load(Path::new(PROGRESS)).unwrap_or_else(|error| {
+        eprintln!("{PROGRESS}: {error}");
+        Progress { levels: vec![] }
+    });
+    let progress = Rc::new(RefCell::new(progress));
+    let registry = registry(progress.clone());
+    let mut high_scores = HighScores::load(Path::new(HIGH_SCORES)).unwrap_or_else(|error| {
+        eprintln!("{HIGH_SCORES}: {error}");
+        HighScores { entries: vec![] }
+    });
+    let mut game_mode = GameMode::Menu;
+    if let Some(path) = std::env::args().nth(1) {
+        match Replay::load(Path::new(&path)) {
+            Ok(replay) => match registry.get(replay.level) {
+                Some(level) => {
+                    game_mode = start(
+                        level,
+                        Tape::Playing(replay, 0),
+                        &mut game_objects,
+                        &mut clock,
+                        &mut history,
+                    );
+                }
+                None => eprintln!("{path}: unknown level {}", replay.level),
+            },
+            Err(error) => eprintln!("{path}: {error}"),
+        }
+    }
     play_sound(
         &music,
         PlaySoundParams {
@@ -53,56 +137,161 @@ async fn main() {
     loop {
         match game_mode {
             GameMode::Menu => {
-                menu::run(&mut game_last_tick, &game_assets, &mut game_objects);
-                if is_key_pressed(KeyCode::Enter) {
-                    game_mode = GameMode::Play(Level::Lvl1);
-                    level1::init(&mut game_last_tick, &mut game_objects);
+                menu::run(&mut clock, &game_assets, &mut game_objects, &high_scores);
+                if is_key_pressed(KeyCode::Enter)
+                    && let Some(level) = registry.first()
+                {
+                    game_objects.score = Score::default();
+                    game_mode = start(
+                        level,
+                        recording(level, &game_objects),
+                        &mut game_objects,
+                        &mut clock,
+                        &mut history,
+                    );
                 }
             }
-            GameMode::Play(ref current_level) => match current_level {
-                Level::Lvl1 => {
-                    level1::run(&mut game_last_tick, &mut game_objects, &game_assets);
+            GameMode::Play(id) => match registry.get(id) {
+                Some(level) => {
+                    level.update(&mut clock, &mut history, &mut game_objects);
+                    level.draw(&clock, &game_objects, &game_assets);
 
                     if is_key_pressed(KeyCode::Enter) {
                         game_mode = GameMode::Menu;
-                    }
-
-                    if game_objects.asteroids.len() == 0 {
-                        game_mode = GameMode::Play(Level::Lvl2);
-                        level2::init(&mut game_last_tick, &mut game_objects);
+                    } else if level.is_lost(&game_objects) {
+                        game_mode = GameMode::GameOver;
+                    } else if level.is_won(&game_objects) {
+                        game_objects.score.points += game_objects.score.time_bonus();
+                        if level.id() != HUB {
+                            let mut progress = progress.borrow_mut();
+                            progress.record(level, &game_objects);
+                            if let Err(error) = progress.save(Path::new(PROGRESS)) {
+                                eprintln!("{PROGRESS}: {error}");
+                            }
+                        }
+                        game_mode = next(
+                            level,
+                            &registry,
+                            &high_scores,
+                            &mut game_objects,
+                            &mut clock,
+                            &mut history,
+                        );
                     }
                 }
-                Level::Lvl2 => {
-                    level2::run(&mut game_last_tick, &mut game_objects, &game_assets);
-
-                    if is_key_pressed(KeyCode::Enter) {
-                        game_mode = GameMode::Menu;
-                    }
-
-                    if game_objects.asteroids.len() == 0 {
-                        game_mode = GameMode::Play(Level::Lvl3);
-                        level3::init(&mut game_last_tick, &mut game_objects);
-                    }
+                None => game_mode = GameMode::Menu,
+            },
+            GameMode::GameOver => {
+                menu::game_over(&game_assets, &game_objects);
+                if is_key_pressed(KeyCode::Enter) {
+                    game_mode = finish(game_objects.score.points, &high_scores);
                 }
-                Level::Lvl3 => {
-                    level3::run(
-                        &mut game_last_tick,
-                        &mut game_mode,
-                        &mut game_objects,
-                        &game_assets,
-                    );
-
-                    if is_key_pressed(KeyCode::Enter) {
-                        game_mode = GameMode::Menu;
+            }
+            GameMode::Initials(ref mut initials) => {
+                while let Some(letter) = get_char_pressed() {
+                    if letter.is_ascii_alphabetic() && initials.len() < 3 {
+                        initials.push(letter.to_ascii_uppercase());
                     }
-
-                    if game_objects.asteroids.len() == 0 {
-                        game_mode = GameMode::Menu;
-                        menu::init();
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    initials.pop();
+                }
+                menu::initials(&game_assets, initials, game_objects.score.points);
+                if is_key_pressed(KeyCode::Enter) && !initials.is_empty() {
+                    high_scores.insert(initials, game_objects.score.points);
+                    if let Err(error) = high_scores.save(Path::new(HIGH_SCORES)) {
+                        eprintln!("{HIGH_SCORES}: {error}");
                     }
+                    menu::init();
+                    game_mode = GameMode::Menu;
                 }
-            },
+            }
         };
+
+        if let GameMode::Play(_) = game_mode {
+            if is_key_pressed(KeyCode::F5) {
+                let replay = clock.tape.replay();
+                let path =
+                    PathBuf::from(format!("replays/{}-{}.replay", replay.level, replay.seed));
+                match clock.tape.save(&path, &game_objects) {
+                    Ok(()) => println!("saved replay to {}", path.display()),
+                    Err(error) => eprintln!("{}: {error}", path.display()),
+                }
+            }
+            if clock.tape.is_finished() {
+                if clock.tape.verify(&game_objects) {
+                    println!("replay finished in sync");
+                } else {
+                    eprintln!("replay finished out of sync");
+                }
+                clock.tape.take_over();
+            }
+        }
         next_frame().await;
     }
 }
+
+// The new level eases in from the physics the world has right now.
+fn recording(level: &dyn Level, game_objects: &GameObjects) -> Tape {
+    let seed = macroquad::miniquad::date::now().to_bits();
+    Tape::Recording(Replay::new(
+        level.id(),
+        seed,
+        screen_bounds(),
+        game_objects.profile,
+    ))
+}
+
+// Starts recording the level after `level`, or ends the run after the last one.
+fn next(
+    level: &dyn Level,
+    registry: &Registry,
+    high_scores: &HighScores,
+    game_objects: &mut GameObjects,
+    clock: &mut Clock,
+    history: &mut History,
+) -> GameMode {
+    let Some(id) = level.next(game_objects) else {
+        return finish(game_objects.score.points, high_scores);
+    };
+    match registry.get(id) {
+        Some(next) => {
+            let tape = recording(next, game_objects);
+            start(next, tape, game_objects, clock, history)
+        }
+        None => {
+            eprintln!("level {} leads to unknown level {id}", level.id());
+            GameMode::Menu
+        }
+    }
+}
+
+// Asks for initials when the run made it into the high scores.
+fn finish(points: u32, high_scores: &HighScores) -> GameMode {
+    if !high_scores.qualifies(points) {
+        menu::init();
+        return GameMode::Menu;
+    }
+    // letters typed while playing are still queued
+    while get_char_pressed().is_some() {}
+    GameMode::Initials(String::new())
+}
+
+// Sets up `level` with the seed, bounds and starting profile of `tape`, and
+// resets the clock to it.
+fn start(
+    level: &dyn Level,
+    tape: Tape,
+    game_objects: &mut GameObjects,
+    clock: &mut Clock,
+    history: &mut History,
+) -> GameMode {
+    game_objects.seed = tape.replay().seed;
+    game_objects.bounds = tape.replay().bounds;
+    game_objects.profile = tape.replay().profile;
+    level.init(game_objects);
+    game_objects.profile_target = level.profile();
+    clock.reset(tape);
+    history.clear();
+    GameMode::Play(level.id())
+}
@@ -1,15 +1,21 @@
-use crate::collision;
-use crate::Level;
+use crate::{centroid, decompose, mass_properties, wind, Aabb, Bounds, Profile, Rng, Score};
 use crate::{
-    draw_circle_lines, draw_poly_lines, draw_texture, draw_texture_ex, draw_triangle_lines,
-    is_key_down, is_key_pressed, screen_height, screen_width, Color, DrawTextureParams, KeyCode,
-    Mat2, Rect, Texture2D, Vec2, ORANGE, WHITE,
+    draw_circle_lines, draw_line, draw_rectangle, draw_texture, draw_texture_ex,
+    draw_triangle_lines, Color, DrawTextureParams, Mat2, Rect, Texture2D, Vec2, GRAY, ORANGE,
+    WHITE,
 };
+use crate::{Clock, History};
+use crate::{TimeField, Well};
 use crate::{PI, TAU};
+use std::rc::Rc;
 
 pub enum GameMode {
     Menu,
-    Play(Level),
+    // id of the level in the registry
+    Play(u8),
+    GameOver,
+    // a high score waiting for the player's initials
+    Initials(String),
 }
 
 pub struct GameAssets {
@@ -18,11 +24,31 @@ pub struct GameAssets {
     pub blob_dark: Texture2D,
 }
 
+#[derive(Clone)]
 pub struct GameObjects {
     pub ship: Ship,
     pub asteroids: Vec<Asteroid>,
     pub bullets: Vec<Bullet>,
     pub smokes: Vec<Smoke>,
+    pub debris: Vec<Debris>,
+    pub obstacles: Vec<Obstacle>,
+    pub shadow: Option<Shadow>,
+    pub casts_shadow: bool,
+    // bullets fired from now on keep flying while the world rewinds
+    pub immune_bullets: bool,
+    pub time_fields: Vec<TimeField>,
+    pub field_charges: u8,
+    pub wells: Vec<Well>,
+    // ships left after the current one, and the countdown while it is wrecked
+    pub lives: u8,
+    pub respawn_in: Option<f32>,
+    pub score: Score,
+    // the physics right now, easing towards the level's own
+    pub profile: Profile,
+    pub profile_target: Profile,
+    pub bounds: Bounds,
+    // everything random in a level derives from this, replays store it
+    pub seed: u64,
 }
 
 impl Shape for Rect {
@@ -31,7 +57,6 @@ impl Shape for Rect {
         let left_top = Vec2::new(self.x, self.y);
         vertices.push(left_top);
         vertices.push(left_top + Vec2 { x: self.w, y: 0.0 });
-        vertices.push(left_top + Vec2 { x: 0.0, y: self.h });
         vertices.push(
             left_top
                 + Vec2 {
@@ -39,23 +64,37 @@ impl Shape for Rect {
                     y: self.h,
                 },
         );
+        vertices.push(left_top + Vec2 { x: 0.0, y: self.h });
 
         return vertices;
     }
 }
 
-pub struct Smoke {
-    body: Body,
-    size: f32,
-    timer: f32,
+#[derive(Clone)]
+pub struct Obstacle {
+    pub rect: Rect,
+    pub time_immune: bool,
 }
 
-impl Update for Smoke {
-    fn update(&mut self, dt: f32) -> () {
-        self.timer += dt;
+impl Shape for Obstacle {
+    fn shape(&self) -> Vec<Vec2> {
+        self.rect.shape()
     }
 }
 
+impl Draw for Obstacle {
+    fn draw(&self, _spritesheet: Option<&Texture2D>) {
+        draw_rectangle(self.rect.x, self.rect.y, self.rect.w, self.rect.h, WHITE);
+    }
+}
+
+#[derive(Clone)]
+pub struct Smoke {
+    pub body: Body,
+    pub size: f32,
+    pub timer: f32,
+}
+
 impl Draw for Smoke {
     fn draw(&self, spritesheet: Option<&macroquad::texture::Texture2D>) -> () {
         let scale = 1.0 + self.timer / 12.0;
@@ -78,11 +117,21 @@ impl Draw for Smoke {
     }
 }
 
-fn is_out_of_screen(body: &Body) -> bool {
-    return body.lin_pos.x <= 0.0
-        || body.lin_pos.y <= 0.0
-        || body.lin_pos.x >= screen_width()
-        || body.lin_pos.y >= screen_height();
+// A piece of a wrecked ship, `half` is half of its segment when unrotated.
+#[derive(Clone)]
+pub struct Debris {
+    pub body: Body,
+    pub half: Vec2,
+    pub timer: f32,
+}
+
+impl Draw for Debris {
+    fn draw(&self, _spritesheet: Option<&Texture2D>) {
+        let half = Mat2::from_angle(self.body.ang_pos) * self.half;
+        let (a, b) = (self.body.lin_pos - half, self.body.lin_pos + half);
+        let color = Color::new(ORANGE.r, ORANGE.g, ORANGE.b, 1.0 - self.timer / 1.5);
+        draw_line(a.x, a.y, b.x, b.y, 3.0, color);
+    }
 }
 
 // CONSTANTS
@@ -94,33 +143,199 @@ pub trait Update {
     fn update(&mut self, dt: f32) -> ();
 }
 
+// Bodies moving under what the world around them exerts, lent by `GameObjects`
+// for one step instead of copied into every body.
+pub trait Advance {
+    fn advance(&mut self, dt: f32, field: &Field);
+}
+
+pub struct Field<'a> {
+    // [Gravity] sampled by the integrator wherever it evaluates a body
+    pub wells: &'a [Well],
+    // [LevelDifficulty] eased towards the level's, see `GameObjects::tune`
+    pub profile: Profile,
+}
+
+// Time-immune objects keep moving forward while the rest of the world rewinds.
+pub trait TimeImmune {
+    fn time_immune(&self) -> bool;
+}
+
+// [Levels]
+// Everything `main` needs to play a level, whatever it is made of. Levels are
+// looked up by id in the `Registry`, replays store that id.
+pub trait Level {
+    fn id(&self) -> u8;
+
+    // Resets the whole world, seeded with `game_objects.seed`.
+    fn init(&self, game_objects: &mut GameObjects);
+
+    fn update(&self, clock: &mut Clock, history: &mut History, game_objects: &mut GameObjects) {
+        clock.advance(history, game_objects);
+    }
+
+    fn draw(&self, clock: &Clock, game_objects: &GameObjects, game_assets: &GameAssets);
+
+    fn is_won(&self, game_objects: &GameObjects) -> bool;
+
+    fn is_lost(&self, game_objects: &GameObjects) -> bool {
+        game_objects.is_game_over()
+    }
+
+    // How the ship handles here, the world eases into it.
+    fn profile(&self) -> Profile {
+        Profile::default()
+    }
+
+    // Where winning leads to, `None` ends the run.
+    fn next(&self, game_objects: &GameObjects) -> Option<u8>;
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Input {
+    pub thrust: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    pub drop_field: bool,
+    pub rewind: bool,
+}
+
+#[derive(Clone)]
 pub struct Ship {
     pub body: Body,
+    pub input: Input,
+    // seconds left during which asteroids only bounce off
+    pub invulnerable: f32,
+}
+
+// A ghost of the ship replaying the inputs of a timeline that was rewound and
+// then overwritten.
+#[derive(Clone)]
+pub struct Shadow {
+    pub ship: Ship,
+    pub inputs: Rc<[Input]>,
+    pub tick: usize,
+}
+
+impl Draw for Shadow {
+    fn draw(&self, _spritesheet: Option<&Texture2D>) {
+        let vertices = self.ship.shape();
+        draw_triangle_lines(vertices[0], vertices[1], vertices[2], 3.0, GRAY);
+    }
 }
 
 impl Default for Ship {
     fn default() -> Ship {
-        Ship {
-            body: Body {
-                lin_pos: Vec2 { x: 535.0, y: 55.0 },
-                lin_vel: Vec2 { x: 0.0, y: 0.0 },
-                lin_acc: Vec2 { x: 0.0, y: 0.0 },
-                ang_pos: 3.0 / 4.0 * TAU,
-                ang_vel: 0.0,
-                ang_acc: 0.0,
-            },
-        }
+        Ship::new(Body {
+            lin_pos: Vec2 { x: 535.0, y: 55.0 },
+            ang_pos: 3.0 / 4.0 * TAU,
+            ..Default::default()
+        })
     }
 }
 
 #[derive(Clone)]
 pub struct Asteroid {
     pub body: Body,
-    pub sides: u8,
     pub size: f32,
+    pub time_immune: bool,
+    // vertices around `body.lin_pos` before rotation
+    pub outline: Vec<Vec2>,
+    // convex pieces of `outline`, in the same frame
+    pub parts: Vec<Vec<Vec2>>,
+}
+
+// Mass per unit of area, shared by everything so that size alone decides weight.
+pub const DENSITY: f32 = 1.0;
+
+impl Asteroid {
+    // A rock of roughly `size` radius: corners at jittered angles and
+    // distances, now and then notched deep enough to make it concave.
+    pub fn jagged(body: Body, size: f32, rng: &mut Rng, time_immune: bool) -> Asteroid {
+        let corners = 7 + (rng.next_u64() % 6) as usize;
+        let theta = TAU / corners as f32;
+        let outline: Vec<Vec2> = (0..corners)
+            .map(|it| {
+                let angle = (it as f32 + rng.range(-0.3, 0.3)) * theta;
+                let mut radius = size * rng.range(0.7, 1.0);
+                if rng.next_f32() < 0.25 {
+                    radius *= 0.55;
+                }
+                radius * Vec2::new(angle.cos(), angle.sin())
+            })
+            .collect();
+        // the body sits on the centre of mass, not on the centre of the circle
+        let centroid = centroid(&outline);
+        let outline = outline.iter().map(|vertice| *vertice - centroid).collect();
+
+        let mut asteroid = Asteroid::from_outline(body, outline, time_immune);
+        asteroid.body.set_mass(&asteroid.shape(), DENSITY);
+        asteroid
+    }
+
+    // Keeps the mass already in `body`, fragments bring their own. The outline
+    // may be concave, collisions use its convex parts.
+    pub fn from_outline(body: Body, outline: Vec<Vec2>, time_immune: bool) -> Asteroid {
+        let outline = wind(outline);
+        Asteroid {
+            body,
+            size: outline
+                .iter()
+                .map(|vertice| vertice.length())
+                .fold(0.0, f32::max),
+            time_immune,
+            parts: decompose(&outline),
+            outline,
+        }
+    }
+}
+
+impl Ship {
+    pub fn new(body: Body) -> Ship {
+        let mut ship = Ship {
+            body,
+            input: Default::default(),
+            invulnerable: 0.0,
+        };
+        ship.body.set_mass(&ship.shape(), DENSITY);
+        ship
+    }
+}
+
+impl Obstacle {
+    // A static body at the centre of the wall, whatever hits it takes the
+    // whole impulse.
+    pub fn body(&self) -> Body {
+        Body {
+            lin_pos: Vec2::new(
+                self.rect.x + self.rect.w / 2.0,
+                self.rect.y + self.rect.h / 2.0,
+            ),
+            mass: f32::INFINITY,
+            inertia: f32::INFINITY,
+            ..Default::default()
+        }
+    }
+}
+
+impl TimeImmune for Asteroid {
+    fn time_immune(&self) -> bool {
+        self.time_immune
+    }
 }
 
-impl Asteroid {}
+impl TimeImmune for Bullet {
+    fn time_immune(&self) -> bool {
+        self.time_immune
+    }
+}
+
+impl TimeImmune for Obstacle {
+    fn time_immune(&self) -> bool {
+        self.time_immune
+    }
+}
 
 #[derive(Clone)]
 pub struct Body {
@@ -131,6 +346,31 @@ pub struct Body {
     pub ang_pos: f32,
     pub ang_vel: f32,
     pub ang_acc: f32,
+
+    // infinite mass and inertia make a body immovable
+    pub mass: f32,
+    pub inertia: f32,
+    pub restitution: f32,
+    pub friction: f32,
+
+    // accumulated until the next update, use `apply_force` and `apply_torque`
+    pub force: Vec2,
+    pub torque: f32,
+
+    pub time_scale: f32,
+    pub integrator: Integrator,
+}
+
+// [Integrators]
+// How `Body::advance` steps position and velocity forward. Semi-implicit Euler
+// is cheap and what the game was tuned with, the others trade speed for
+// accuracy when forces change quickly with position or velocity.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum Integrator {
+    #[default]
+    SemiImplicitEuler,
+    VelocityVerlet,
+    Rk4,
 }
 
 impl Default for Body {
@@ -142,51 +382,38 @@ impl Default for Body {
             ang_pos: TAU / 2.0,
             ang_vel: 0.0,
             ang_acc: 0.0,
+            mass: 1.0,
+            inertia: 1.0,
+            restitution: 0.8,
+            friction: 0.3,
+            force: Vec2 { x: 0.0, y: 0.0 },
+            torque: 0.0,
+            time_scale: 1.0,
+            integrator: Integrator::default(),
         }
     }
 }
 
-impl Update for Body {
-    fn update(&mut self, dt: f32) -> () {
-        let (lin_fric, ang_fric) = {
-            // [DragClamp]
-            (
-                -1.0 * if self.lin_vel.length() > 1000.0 {
-                    1.0 * self.lin_vel
-                } else {
-                    Vec2 { x: 0.0, y: 0.0 }
-                },
-                if self.ang_vel.abs() > 15.0 {
-                    15.0 * self.ang_vel / self.ang_vel.abs()
-                } else {
-                    0.0
-                },
-            )
-        };
+impl Body {
+    // Mass and moment of inertia of a uniform polygon around `lin_pos`.
+    pub fn set_mass(&mut self, vertices: &[Vec2], density: f32) {
+        let (mass, inertia) = mass_properties(vertices, self.lin_pos, density);
+        self.mass = mass;
+        self.inertia = inertia;
+    }
 
-        self.lin_acc += lin_fric;
-        self.lin_vel += self.lin_acc * dt;
-        self.lin_pos += self.lin_vel * dt;
-
-        self.ang_acc += ang_fric;
-        self.ang_vel += self.ang_acc * dt;
-        self.ang_pos += self.ang_vel * dt;
-
-        {
-            // warping
-            let x = &mut self.lin_pos.x;
-            let y = &mut self.lin_pos.y;
-            if *x < 0.0 {
-                *x = screen_width();
-            } else if *x > screen_width() {
-                *x = 0.0;
-            };
-            if *y < 0.0 {
-                *y = screen_height();
-            } else if *y > screen_height() {
-                *y = 0.0;
-            };
-        }
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    // Instant change of momentum, `offset` is where it hits relative to `lin_pos`.
+    pub fn apply_impulse(&mut self, impulse: Vec2, offset: Vec2) {
+        self.lin_vel += impulse / self.mass;
+        self.ang_vel += offset.perp_dot(impulse) / self.inertia;
     }
 }
 
@@ -208,83 +435,36 @@ impl Draw for Ship {
             ),
             None => {}
         } */
+        // blinks while invulnerable
+        if (self.invulnerable * 10.0) as i32 % 2 == 1 {
+            return;
+        }
         let vertices = self.shape();
         debug_assert!(vertices.len() == 3);
         draw_triangle_lines(vertices[0], vertices[1], vertices[2], 5.0, ORANGE);
     }
 }
 
-impl Update for Ship {
-    fn update(&mut self, dt: f32) -> () {
-        let input_left = is_key_down(KeyCode::Left) || is_key_down(KeyCode::J);
-        let input_right = is_key_down(KeyCode::Right) || is_key_down(KeyCode::L);
-
-        let lin_boost: Vec2 = if is_key_down(KeyCode::Up) || is_key_down(KeyCode::I) {
-            -4000.0
-                * Vec2 {
-                    x: self.body.ang_pos.cos(),
-                    y: self.body.ang_pos.sin(),
-                }
-        } else {
-            // [AutoBreak]
-            -10.0 * self.body.lin_vel
-        };
-        let ang_boost: f32 = if input_left {
-                -50.0
-            } else if input_right {
-                50.0
-            } else {
-                0.0
-            }
-            // [AutoBreak]
-             + if !input_left && !input_right {
-                    -15.0*self.body.ang_vel
-                }
-                else{0.0};
-
-        self.body.lin_acc = lin_boost;
-        self.body.ang_acc = ang_boost;
-
-        // clamp
-        if self.body.lin_vel.length() > 1000.0 {
-            self.body.lin_vel = 1000.0 * self.body.lin_vel / self.body.lin_vel.length();
-        }
-        if self.body.ang_vel.abs() > 3.0 {
-            self.body.ang_vel = 3.0 * self.body.ang_vel / self.body.ang_vel.abs();
-        }
-        self.body.update(dt);
-    }
-}
-
-impl Update for Asteroid {
-    fn update(&mut self, dt: f32) -> () {
-        self.body.ang_acc = 0.0;
-        self.body.lin_acc = Vec2 { x: 0.0, y: 0.0 };
-        self.body.update(dt);
-    }
-}
-
 impl Draw for Asteroid {
     fn draw(&self, texture: Option<&Texture2D>) -> () {
         match texture {
             Some(texture) => draw_texture(texture, self.body.lin_pos.x, self.body.lin_pos.y, WHITE),
             None => {
-                draw_poly_lines(
-                    self.body.lin_pos.x,
-                    self.body.lin_pos.y,
-                    self.sides,
-                    self.size,
-                    self.body.ang_pos.to_degrees(),
-                    2.0,
-                    WHITE,
-                );
+                let vertices = self.shape();
+                for it in 0..vertices.len() {
+                    let a = vertices[it];
+                    let b = vertices[(it + 1) % vertices.len()];
+                    draw_line(a.x, a.y, b.x, b.y, 2.0, WHITE);
+                }
             }
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Bullet {
-    body: Body,
+    pub body: Body,
+    pub time_immune: bool,
 }
 
 impl Draw for Bullet {
@@ -310,144 +490,139 @@ impl Draw for Bullet {
 
 impl Draw for GameObjects {
     fn draw(&self, spritesheet: Option<&Texture2D>) -> () {
+        let bounds = self.bounds;
         self.bullets
             .iter()
             .for_each(|bullet| bullet.draw(spritesheet));
-        self.asteroids
-            .iter()
-            .for_each(|asteroid| asteroid.draw(None));
-        self.smokes.iter().for_each(|smoke| smoke.draw(spritesheet));
-        self.ship.draw(spritesheet);
-    }
-}
-
-impl Update for Bullet {
-    fn update(&mut self, dt: f32) {
-        self.body.ang_acc = 0.0;
-        self.body.lin_acc = Vec2 { x: 0.0, y: 0.0 };
-        self.body.update(dt);
-    }
-}
-
-impl Update for GameObjects {
-    fn update(&mut self, dt: f32) {
-        self.ship.update(dt);
-        self.bullets.iter_mut().for_each(|bullet| bullet.update(dt));
-        self.asteroids
-            .iter_mut()
-            .for_each(|asteroid| asteroid.update(dt));
-        self.smokes.iter_mut().for_each(|smoke| smoke.update(dt));
-
-        if is_key_pressed(KeyCode::F) {
-            self.bullets.push(Bullet {
-                body: Body {
-                    lin_pos: self.ship.shape()[0],
-                    lin_vel: -1000.0
-                        * Vec2 {
-                            x: self.ship.body.ang_pos.cos(),
-                            y: self.ship.body.ang_pos.sin(),
-                        }
-                        + 0.2 * self.ship.body.lin_vel,
-                    lin_acc: Vec2 { x: 0.0, y: 0.0 },
-                    ang_pos: self.ship.body.ang_pos + TAU / 2.0,
-                    ang_vel: 0.0,
-                    ang_acc: 0.0,
-                },
+        self.asteroids.iter().for_each(|asteroid| {
+            draw_wrapped(asteroid, Aabb::of(asteroid), bounds, None, |asteroid| {
+                &mut asteroid.body
             })
-        }
-
-        let mut new_asteroids: Vec<Asteroid> = vec![];
-        self.bullets
-            .retain(|bullet| !is_out_of_screen(&bullet.body));
-        self.asteroids.retain(|asteroid| {
-            let mut asteroid_collided = false;
-            let rotation_theta = 0.25 * TAU;
-
-            for bullet in &self.bullets {
-                let collided = collision(bullet, asteroid);
-                if let Some(_) = collided {
-                    self.smokes.push(Smoke {
-                        body: Body {
-                            lin_pos: asteroid.body.lin_pos,
-                            ..Default::default()
-                        },
-                        timer: 0.0,
-                        size: 2.0 * asteroid.size,
-                    });
-                    if asteroid.sides > 3 {
-                        vec![
-                            Asteroid {
-                                body: Body {
-                                    lin_pos: asteroid.body.lin_pos,
-                                    lin_vel: asteroid.body.lin_vel
-                                        - Mat2 {
-                                            x_axis: Vec2 {
-                                                x: rotation_theta.cos(),
-                                                y: -rotation_theta.sin(),
-                                            },
-                                            y_axis: Vec2 {
-                                                x: rotation_theta.sin(),
-                                                y: -rotation_theta.cos(),
-                                            },
-                                        } * 0.2
-                                            * bullet.body.lin_vel,
-                                    lin_acc: Vec2 { x: 0.0, y: 0.0 },
-                                    ang_pos: asteroid.body.ang_pos,
-                                    ang_vel: asteroid.body.ang_vel,
-                                    ang_acc: 0.0,
-                                },
-                                sides: asteroid.sides - 1,
-                                size: 2.0 * asteroid.size / 3.0,
-                            },
-                            Asteroid {
-                                body: Body {
-                                    lin_pos: asteroid.body.lin_pos,
-                                    lin_vel: asteroid.body.lin_vel
-                                        + Mat2 {
-                                            x_axis: Vec2 {
-                                                x: rotation_theta.cos(),
-                                                y: -rotation_theta.sin(),
-                                            },
-                                            y_axis: Vec2 {
-                                                x: rotation_theta.sin(),
-                                                y: rotation_theta.cos(),
-                                            },
-                                        } * 0.2
-                                            * bullet.body.lin_vel,
-                                    lin_acc: Vec2 { x: 0.0, y: 0.0 },
-                                    ang_pos: asteroid.body.ang_pos,
-                                    ang_vel: asteroid.body.ang_vel,
-                                    ang_acc: 0.0,
-                                },
-                                sides: asteroid.sides - 1,
-                                size: 2.0 * asteroid.size / 3.0,
-                            },
-                        ]
-                        .iter()
-                        .for_each(|new_asteroid| new_asteroids.push(new_asteroid.clone()))
-                    }
-                };
-                asteroid_collided = if let Some(_) = collided { true } else { false };
-                if let Some(_) = collided {
-                    break;
-                }
-            }
-            !asteroid_collided
         });
-        self.smokes.retain(|smoke| smoke.timer < 3.0);
-        new_asteroids
+        self.smokes.iter().for_each(|smoke| smoke.draw(spritesheet));
+        self.debris
             .iter()
-            .for_each(|new_asteroid| self.asteroids.push(new_asteroid.clone()));
-        for asteroid in &self.asteroids {
-            if let Some(_) = collision(&self.ship, asteroid) {
-                //*game_mode = GameMode::Menu;
-            }
+            .for_each(|debris| debris.draw(spritesheet));
+        self.time_fields
+            .iter()
+            .for_each(|time_field| time_field.draw(spritesheet));
+        self.wells.iter().for_each(|well| well.draw(spritesheet));
+        self.obstacles
+            .iter()
+            .for_each(|obstacle| obstacle.draw(spritesheet));
+        if let Some(shadow) = &self.shadow {
+            draw_wrapped(
+                shadow,
+                Aabb::of(&shadow.ship),
+                bounds,
+                spritesheet,
+                |shadow| &mut shadow.ship.body,
+            );
         }
+        if self.respawn_in.is_none() {
+            draw_wrapped(
+                &self.ship,
+                Aabb::of(&self.ship),
+                bounds,
+                spritesheet,
+                |ship| &mut ship.body,
+            );
+        }
+
+        // lives left, as small ships in the top right corner
+        for it in 0..self.lives {
+            let center = Vec2::new(bounds.width - 30.0 - 25.0 * it as f32, 30.0);
+            let up = Vec2::new(0.0, -10.0);
+            draw_triangle_lines(
+                center + up,
+                center - up + up.perp() * 0.6,
+                center - up - up.perp() * 0.6,
+                2.0,
+                ORANGE,
+            );
+        }
+        self.score.draw(spritesheet);
+    }
+}
+
+// [Warping]
+// Whatever pokes across an edge is drawn a second time on the opposite side,
+// where collisions already see it.
+fn draw_wrapped<T: Draw + Clone>(
+    object: &T,
+    aabb: Aabb,
+    bounds: Bounds,
+    spritesheet: Option<&Texture2D>,
+    body: fn(&mut T) -> &mut Body,
+) {
+    object.draw(spritesheet);
+    for offset in bounds.images(&aabb) {
+        let mut ghost = object.clone();
+        body(&mut ghost).lin_pos += offset;
+        ghost.draw(spritesheet);
     }
 }
 
 pub trait Shape {
+    // Outline as a polygon, round shapes give an approximation.
     fn shape(&self) -> Vec<Vec2>;
+
+    // What the narrowphase actually tests, concave shapes split themselves up
+    // and round ones are described exactly.
+    fn parts(&self) -> Vec<Primitive> {
+        vec![Primitive::Polygon(self.shape())]
+    }
+}
+
+// [RoundShapes]
+// A circle is a capsule whose segment has no length.
+#[derive(Clone)]
+pub enum Primitive {
+    // convex and positively wound
+    Polygon(Vec<Vec2>),
+    Circle { center: Vec2, radius: f32 },
+    Capsule { a: Vec2, b: Vec2, radius: f32 },
+}
+
+impl Primitive {
+    pub fn center(&self) -> Vec2 {
+        match self {
+            Primitive::Polygon(vertices) => {
+                vertices.iter().copied().sum::<Vec2>() / vertices.len() as f32
+            }
+            Primitive::Circle { center, .. } => *center,
+            Primitive::Capsule { a, b, .. } => (*a + *b) / 2.0,
+        }
+    }
+
+    pub fn translate(&mut self, by: Vec2) {
+        match self {
+            Primitive::Polygon(vertices) => vertices.iter_mut().for_each(|vertice| *vertice += by),
+            Primitive::Circle { center, .. } => *center += by,
+            Primitive::Capsule { a, b, .. } => {
+                *a += by;
+                *b += by;
+            }
+        }
+    }
+
+    // Smallest and largest corner of the box around it.
+    pub fn extent(&self) -> (Vec2, Vec2) {
+        match self {
+            Primitive::Polygon(vertices) => vertices.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(min, max), vertice| (min.min(*vertice), max.max(*vertice)),
+            ),
+            Primitive::Circle { center, radius } => (
+                *center - Vec2::splat(*radius),
+                *center + Vec2::splat(*radius),
+            ),
+            Primitive::Capsule { a, b, radius } => (
+                a.min(*b) - Vec2::splat(*radius),
+                a.max(*b) + Vec2::splat(*radius),
+            ),
+        }
+    }
 }
 
 impl Shape for Ship {
@@ -478,22 +653,28 @@ impl Shape for Ship {
 impl Shape for Asteroid {
     fn shape(&self) -> Vec<Vec2> {
         let center = self.body.lin_pos;
-        let radius = self.size;
-        let rotation = self.body.ang_pos;
+        let rotation = Mat2::from_angle(self.body.ang_pos);
 
-        let mut vertices = vec![];
-        let theta = TAU / self.sides as f32;
-        for it in 0..self.sides {
-            vertices.push(
-                center
-                    + radius
-                        * Vec2::new(
-                            (it as f32 * theta + rotation).cos(),
-                            (it as f32 * theta + rotation).sin(),
-                        ),
-            );
-        }
-        return vertices;
+        self.outline
+            .iter()
+            .map(|vertice| center + rotation * *vertice)
+            .collect()
+    }
+
+    fn parts(&self) -> Vec<Primitive> {
+        let center = self.body.lin_pos;
+        let rotation = Mat2::from_angle(self.body.ang_pos);
+
+        self.parts
+            .iter()
+            .map(|part| {
+                Primitive::Polygon(
+                    part.iter()
+                        .map(|vertice| center + rotation * *vertice)
+                        .collect(),
+                )
+            })
+            .collect()
     }
 }
 
@@ -510,7 +691,6 @@ impl Shape for Bullet {
             };
         vertices.push(left_top);
         vertices.push(left_top + Vec2 { x: width, y: 0.0 });
-        vertices.push(left_top + Vec2 { x: 0.0, y: height });
         vertices.push(
             left_top
                 + Vec2 {
@@ -518,6 +698,16 @@ impl Shape for Bullet {
                     y: height,
                 },
         );
+        vertices.push(left_top + Vec2 { x: 0.0, y: height });
         vertices
     }
+    // rounded along its flight, as long and as thick as the box above
+    fn parts(&self) -> Vec<Primitive> {
+        let half = 2.5 * Vec2::from_angle(self.body.ang_pos);
+        vec![Primitive::Capsule {
+            a: self.body.lin_pos - half,
+            b: self.body.lin_pos + half,
+            radius: 2.5,
+        }]
+    }
 }
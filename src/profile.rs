@@ -0,0 +1,94 @@
+// [LevelDifficulty] [DragClamp]
+// How the ship handles and where drag kicks in. Early levels are tuned to feel
+// earth-brained: weak thrust, strong brakes and low speed limits. Later ones
+// get more sensitive and let go of the drag. Each level supplies one, and the
+// world eases from the previous level's profile into it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Profile {
+    // ship acceleration in px/s^2 and angular acceleration in rad/s^2
+    pub thrust: f32,
+    pub turn: f32,
+    // [AutoBreak] how hard the ship slows down without input
+    pub lin_brake: f32,
+    pub ang_brake: f32,
+    // the ship never goes faster than these
+    pub max_lin_vel: f32,
+    pub max_ang_vel: f32,
+    // any body faster than these feels drag
+    pub lin_drag_above: f32,
+    pub ang_drag_above: f32,
+    // how hard spin past `ang_drag_above` is slowed down, in rad/s^2
+    pub ang_drag: f32,
+}
+
+// Seconds for the blend to get about two thirds of the way.
+const EASE_TIME: f32 = 0.75;
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            thrust: 4000.0,
+            turn: 50.0,
+            lin_brake: 10.0,
+            ang_brake: 15.0,
+            max_lin_vel: 1000.0,
+            max_ang_vel: 3.0,
+            lin_drag_above: 1000.0,
+            ang_drag_above: 15.0,
+            ang_drag: 15.0,
+        }
+    }
+}
+
+impl Profile {
+    pub fn blend(&self, to: &Profile, alpha: f32) -> Profile {
+        let mix = |from: f32, to: f32| from + (to - from) * alpha;
+        Profile {
+            thrust: mix(self.thrust, to.thrust),
+            turn: mix(self.turn, to.turn),
+            lin_brake: mix(self.lin_brake, to.lin_brake),
+            ang_brake: mix(self.ang_brake, to.ang_brake),
+            max_lin_vel: mix(self.max_lin_vel, to.max_lin_vel),
+            max_ang_vel: mix(self.max_ang_vel, to.max_ang_vel),
+            lin_drag_above: mix(self.lin_drag_above, to.lin_drag_above),
+            ang_drag_above: mix(self.ang_drag_above, to.ang_drag_above),
+            ang_drag: mix(self.ang_drag, to.ang_drag),
+        }
+    }
+
+    // One tick of easing towards `target`, frame rate independent.
+    pub fn ease(&self, target: &Profile, dt: f32) -> Profile {
+        self.blend(target, 1.0 - (-dt / EASE_TIME).exp())
+    }
+
+    pub fn from_fields(fields: [f32; 9]) -> Profile {
+        let [thrust, turn, lin_brake, ang_brake, max_lin_vel, max_ang_vel, lin_drag_above, ang_drag_above, ang_drag] =
+            fields;
+        Profile {
+            thrust,
+            turn,
+            lin_brake,
+            ang_brake,
+            max_lin_vel,
+            max_ang_vel,
+            lin_drag_above,
+            ang_drag_above,
+            ang_drag,
+        }
+    }
+
+    // In declaration order, how replays store a profile.
+    pub fn fields(&self) -> [f32; 9] {
+        [
+            self.thrust,
+            self.turn,
+            self.lin_brake,
+            self.ang_brake,
+            self.max_lin_vel,
+            self.max_ang_vel,
+            self.lin_drag_above,
+            self.ang_drag_above,
+            self.ang_drag,
+        ]
+    }
+}
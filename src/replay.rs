@@ -0,0 +1,312 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{invalid, Bounds, GameObjects, Input, Profile};
+
+// [Replay]
+// A replay is everything the simulation needs to play a level again: the level,
+// the seed, the world bounds, the physics profile the level eased in from and
+// the input of every tick. Inputs are stored as
+// one bitflag byte each and run-length encoded, held keys compress very well.
+//
+// layout (little endian):
+//   b"BRDR" version:u8 level:u8 seed:u64 width:f32 height:f32 ticks:u32 checksum:u64
+//   profile:[f32; 9] in `Profile::fields` order
+//   then (flags:u8 run:u16) until `ticks` inputs are covered
+const MAGIC: &[u8; 4] = b"BRDR";
+const VERSION: u8 = 1;
+
+pub struct Replay {
+    pub level: u8,
+    pub seed: u64,
+    pub bounds: Bounds,
+    pub profile: Profile,
+    pub inputs: Vec<Input>,
+    // checksum of the world after the last input, zero when unknown
+    pub checksum: u64,
+}
+
+impl Replay {
+    pub fn new(level: u8, seed: u64, bounds: Bounds, profile: Profile) -> Replay {
+        Replay {
+            level,
+            seed,
+            bounds,
+            profile,
+            inputs: vec![],
+            checksum: 0,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.level);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.bounds.width.to_le_bytes());
+        bytes.extend_from_slice(&self.bounds.height.to_le_bytes());
+        bytes.extend_from_slice(&(self.inputs.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        for field in self.profile.fields() {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+
+        let mut it = 0;
+        while it < self.inputs.len() {
+            let flags = encode(&self.inputs[it]);
+            let mut run: u16 = 1;
+            while it + (run as usize) < self.inputs.len()
+                && run < u16::MAX
+                && encode(&self.inputs[it + run as usize]) == flags
+            {
+                run += 1;
+            }
+            bytes.push(flags);
+            bytes.extend_from_slice(&run.to_le_bytes());
+            it += run as usize;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bytes)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let bytes = fs::read(path)?;
+        let mut reader = Reader {
+            bytes: &bytes,
+            at: 0,
+        };
+
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not a braideroids replay"));
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(invalid("unsupported replay version"));
+        }
+        let level = reader.take(1)?[0];
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let width = f32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+        let height = f32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+        let ticks = u32::from_le_bytes(reader.take(4)?.try_into().unwrap()) as usize;
+        let checksum = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let mut fields = [0.0; 9];
+        for field in fields.iter_mut() {
+            *field = f32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+        }
+        let profile = Profile::from_fields(fields);
+
+        // `ticks` comes from the file, no more than the runs left can cover
+        let mut inputs = Vec::with_capacity(ticks.min(reader.remaining() / 3 * u16::MAX as usize));
+        while inputs.len() < ticks {
+            let flags = reader.take(1)?[0];
+            let run = u16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+            (0..run).for_each(|_| inputs.push(decode(flags)));
+        }
+        if inputs.len() != ticks {
+            return Err(invalid("replay inputs do not add up"));
+        }
+
+        Ok(Replay {
+            level,
+            seed,
+            bounds: Bounds { width, height },
+            profile,
+            inputs,
+            checksum,
+        })
+    }
+}
+
+// Where the inputs of the running level come from.
+pub enum Tape {
+    Recording(Replay),
+    Playing(Replay, usize),
+}
+
+impl Tape {
+    pub fn replay(&self) -> &Replay {
+        match self {
+            Tape::Recording(replay) | Tape::Playing(replay, _) => replay,
+        }
+    }
+
+    // Records the live input, or swaps it for the next one on the tape.
+    pub fn next(&mut self, live: Input) -> Input {
+        match self {
+            Tape::Recording(replay) => {
+                replay.inputs.push(live);
+                live
+            }
+            Tape::Playing(replay, tick) => {
+                let input = replay.inputs.get(*tick).copied().unwrap_or_default();
+                *tick += 1;
+                input
+            }
+        }
+    }
+
+    // Stores the world checksum next to the inputs, so playback can be checked.
+    pub fn save(&mut self, path: &Path, game_objects: &GameObjects) -> io::Result<()> {
+        let replay = match self {
+            Tape::Recording(replay) | Tape::Playing(replay, _) => replay,
+        };
+        replay.checksum = checksum(game_objects);
+        replay.save(path)
+    }
+
+    // Whether the world matches the recorded one, only meaningful once finished.
+    pub fn verify(&self, game_objects: &GameObjects) -> bool {
+        let replay = self.replay();
+        replay.checksum == 0 || replay.checksum == checksum(game_objects)
+    }
+
+    // Hands the controls back to the player, later inputs extend the same replay.
+    pub fn take_over(&mut self) {
+        if let Tape::Playing(replay, _) = self {
+            let inputs = std::mem::take(&mut replay.inputs);
+            *self = Tape::Recording(Replay { inputs, ..*replay });
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self {
+            Tape::Recording(_) => false,
+            Tape::Playing(replay, tick) => *tick >= replay.inputs.len(),
+        }
+    }
+}
+
+fn encode(input: &Input) -> u8 {
+    (input.thrust as u8)
+        | (input.left as u8) << 1
+        | (input.right as u8) << 2
+        | (input.fire as u8) << 3
+        | (input.drop_field as u8) << 4
+        | (input.rewind as u8) << 5
+}
+
+fn decode(flags: u8) -> Input {
+    Input {
+        thrust: flags & 1 != 0,
+        left: flags & 1 << 1 != 0,
+        right: flags & 1 << 2 != 0,
+        fire: flags & 1 << 3 != 0,
+        drop_field: flags & 1 << 4 != 0,
+        rewind: flags & 1 << 5 != 0,
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let slice = self
+            .bytes
+            .get(self.at..self.at + count)
+            .ok_or_else(|| invalid("replay ends early"))?;
+        self.at += count;
+        Ok(slice)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.at
+    }
+}
+
+// FNV-1a over the bits of every body, cheap and identical on every run.
+pub fn checksum(game_objects: &GameObjects) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |value: f32| {
+        for byte in value.to_bits().to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    let bodies = std::iter::once(&game_objects.ship.body)
+        .chain(game_objects.shadow.iter().map(|shadow| &shadow.ship.body))
+        .chain(game_objects.asteroids.iter().map(|asteroid| &asteroid.body))
+        .chain(game_objects.bullets.iter().map(|bullet| &bullet.body));
+    for body in bodies {
+        feed(body.lin_pos.x);
+        feed(body.lin_pos.y);
+        feed(body.lin_vel.x);
+        feed(body.lin_vel.y);
+        feed(body.ang_pos);
+        feed(body.ang_vel);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_round_trips() {
+        let path = std::env::temp_dir().join("braideroids-round-trip.brdr");
+        let profile = Profile {
+            ang_drag: 7.0,
+            ..Default::default()
+        };
+        let mut replay = Replay::new(
+            3,
+            42,
+            Bounds {
+                width: 1024.0,
+                height: 768.0,
+            },
+            profile,
+        );
+        replay.inputs = (0..300)
+            .map(|it| Input {
+                thrust: it % 7 < 3,
+                fire: it % 5 == 0,
+                ..Default::default()
+            })
+            .collect();
+        replay.checksum = 12345;
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((loaded.level, loaded.seed, loaded.checksum), (3, 42, 12345));
+        assert_eq!(loaded.bounds.width, 1024.0);
+        assert_eq!(loaded.profile, profile);
+        assert_eq!(loaded.inputs.len(), replay.inputs.len());
+        for (loaded, recorded) in loaded.inputs.iter().zip(&replay.inputs) {
+            assert_eq!(encode(loaded), encode(recorded));
+        }
+    }
+
+    #[test]
+    fn replay_claiming_more_ticks_than_it_holds_is_invalid() {
+        let path = std::env::temp_dir().join("braideroids-truncated.brdr");
+        let mut replay = Replay::new(
+            1,
+            7,
+            Bounds {
+                width: 800.0,
+                height: 600.0,
+            },
+            Profile::default(),
+        );
+        replay.inputs = vec![Input::default(); 10];
+        replay.save(&path).unwrap();
+
+        // ticks sits after magic, version, level, seed, width and height
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[22..26].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+        let error = Replay::load(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
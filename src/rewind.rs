@@ -0,0 +1,255 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+use crate::{step, Bounds};
+use crate::{
+    Asteroid, Bullet, Debris, GameObjects, Input, Obstacle, Shadow, Smoke, TimeField, Vec2, Well,
+};
+
+// [TimeReversal]
+// Every frame the whole world is cloned into `frames`. While rewinding the
+// cursor walks backwards through them, and the first forward frame after that
+// drops everything past the cursor, so the game resumes from wherever the
+// player let go.
+pub struct History {
+    frames: VecDeque<GameObjects>,
+    cursor: usize,
+    memory_limit: usize,
+    memory_used: usize,
+    // inputs of the frames cut off by `overwrite` during this rewind, in order,
+    // the shadow replays them after whatever is dropped on release
+    cut_off: Vec<Input>,
+}
+
+impl History {
+    pub fn new(memory_limit: usize) -> History {
+        History {
+            frames: VecDeque::new(),
+            cursor: 0,
+            memory_limit,
+            memory_used: 0,
+            cut_off: vec![],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.cursor = 0;
+        self.memory_used = 0;
+        self.cut_off.clear();
+    }
+
+    fn is_rewinding(&self) -> bool {
+        self.cursor + 1 < self.frames.len()
+    }
+
+    pub fn record(&mut self, game_objects: &GameObjects) {
+        self.resume();
+
+        self.memory_used += footprint(game_objects);
+        self.frames.push_back(game_objects.clone());
+
+        while self.memory_used > self.memory_limit && self.frames.len() > 1 {
+            if let Some(oldest) = self.frames.pop_front() {
+                self.memory_used -= footprint(&oldest);
+            }
+        }
+        self.cursor = self.frames.len() - 1;
+    }
+
+    // Moves one frame back in time, returns false once the oldest frame is reached.
+    // Time-immune objects are not restored, they advance by `dt` instead.
+    pub fn step_back(&mut self, game_objects: &mut GameObjects, dt: f32) -> bool {
+        if self.cursor == 0 || self.frames.is_empty() {
+            return false;
+        }
+        self.cursor -= 1;
+        game_objects.rewind_to(&self.frames[self.cursor], dt);
+        true
+    }
+
+    // Replaces the frame under the cursor, used when time-immune objects change the past.
+    fn overwrite(&mut self, game_objects: &GameObjects) {
+        let mut cut_off: Vec<Input> = self.resume().iter().map(|frame| frame.ship.input).collect();
+        cut_off.append(&mut self.cut_off);
+        self.cut_off = cut_off;
+        if let Some(frame) = self.frames.back_mut() {
+            self.memory_used -= footprint(frame);
+            self.memory_used += footprint(game_objects);
+            *frame = game_objects.clone();
+        }
+    }
+
+    // Drops the frames after the cursor, so that the timeline continues from here.
+    pub fn resume(&mut self) -> Vec<GameObjects> {
+        if !self.is_rewinding() {
+            return vec![];
+        }
+        let overwritten: Vec<GameObjects> = self.frames.drain(self.cursor + 1..).collect();
+        overwritten
+            .iter()
+            .for_each(|frame| self.memory_used -= footprint(frame));
+        overwritten
+    }
+
+    // Either plays the world backwards while rewind is held, or steps it forward
+    // and records the new frame.
+    pub fn update(
+        &mut self,
+        game_objects: &mut GameObjects,
+        input: Input,
+        bounds: Bounds,
+        dt: f32,
+    ) {
+        if input.rewind {
+            if !self.step_back(game_objects, dt) {
+                return;
+            }
+            game_objects.bounce(dt, true);
+            if game_objects.collide(dt, true) {
+                self.overwrite(game_objects);
+            }
+        } else {
+            if self.frames.is_empty() {
+                self.record(game_objects);
+            }
+            let mut inputs: Vec<Input> =
+                self.resume().iter().map(|frame| frame.ship.input).collect();
+            inputs.append(&mut self.cut_off);
+            if game_objects.casts_shadow && !inputs.is_empty() {
+                game_objects.shadow = Some(Shadow::new(game_objects.ship.clone(), inputs));
+            }
+            step(game_objects, input, bounds, dt);
+            self.record(game_objects);
+        }
+    }
+}
+
+fn footprint(game_objects: &GameObjects) -> usize {
+    size_of::<GameObjects>()
+        + game_objects
+            .asteroids
+            .iter()
+            .map(|asteroid| {
+                let vertices = asteroid.outline.len()
+                    + asteroid.parts.iter().map(|part| part.len()).sum::<usize>();
+                size_of::<Asteroid>() + vertices * size_of::<Vec2>()
+            })
+            .sum::<usize>()
+        + game_objects.bullets.len() * size_of::<Bullet>()
+        + game_objects.smokes.len() * size_of::<Smoke>()
+        + game_objects.debris.len() * size_of::<Debris>()
+        + game_objects.obstacles.len() * size_of::<Obstacle>()
+        + game_objects.time_fields.len() * size_of::<TimeField>()
+        + game_objects.wells.len() * size_of::<Well>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::tests::world;
+    use crate::{Asteroid, Body, Rect, Rng, TICK};
+
+    #[test]
+    fn shadow_replays_frames_cut_off_while_rewinding() {
+        let mut history = History::new(usize::MAX);
+        let mut game_objects = world();
+        game_objects.casts_shadow = true;
+        let bounds = game_objects.bounds;
+        // ten ticks turning left, then ten turning right
+        for it in 0..20 {
+            let input = Input {
+                left: it < 10,
+                right: it >= 10,
+                ..Default::default()
+            };
+            history.update(&mut game_objects, input, bounds, TICK);
+        }
+
+        // back into the right turns, where a time-immune collision changes
+        // the past, then further back into the left turns
+        for _ in 0..5 {
+            history.step_back(&mut game_objects, TICK);
+        }
+        history.overwrite(&game_objects);
+        for _ in 0..10 {
+            history.step_back(&mut game_objects, TICK);
+        }
+        history.update(&mut game_objects, Input::default(), bounds, TICK);
+
+        let shadow = game_objects.shadow.expect("a shadow after rewinding");
+        let inputs: Vec<(bool, bool)> = shadow
+            .inputs
+            .iter()
+            .map(|input| (input.left, input.right))
+            .collect();
+        let mut expected = vec![(true, false); 5];
+        expected.extend(vec![(false, true); 10]);
+        assert_eq!(inputs, expected);
+    }
+
+    #[test]
+    fn immune_bullets_keep_flying_while_rewinding() {
+        let mut history = History::new(usize::MAX);
+        let mut game_objects = world();
+        game_objects.immune_bullets = true;
+        let bounds = game_objects.bounds;
+        game_objects.ship.body.lin_pos = Vec2::new(100.0, 300.0);
+        let fire = Input {
+            fire: true,
+            ..Default::default()
+        };
+        history.update(&mut game_objects, fire, bounds, TICK);
+        for _ in 0..10 {
+            history.update(&mut game_objects, Input::default(), bounds, TICK);
+        }
+        let before = game_objects.bullets[0].body.lin_pos.x;
+
+        let rewind = Input {
+            rewind: true,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            history.update(&mut game_objects, rewind, bounds, TICK);
+        }
+        assert_eq!(game_objects.bullets.len(), 1);
+        assert!(game_objects.bullets[0].time_immune);
+        assert!(game_objects.bullets[0].body.lin_pos.x > before);
+    }
+
+    #[test]
+    fn immune_asteroid_bounces_off_a_wall_while_rewinding() {
+        let mut history = History::new(usize::MAX);
+        let mut game_objects = world();
+        let bounds = game_objects.bounds;
+        game_objects.ship.body.lin_pos = Vec2::new(100.0, 100.0);
+        game_objects.obstacles = vec![Obstacle {
+            rect: Rect::new(400.0, 425.0, 20.0, 150.0),
+            time_immune: false,
+        }];
+        game_objects.asteroids = vec![Asteroid::jagged(
+            Body {
+                lin_pos: Vec2::new(50.0, 500.0),
+                lin_vel: Vec2::new(200.0, 0.0),
+                ..Default::default()
+            },
+            20.0,
+            &mut Rng::new(1),
+            true,
+        )];
+        for _ in 0..120 {
+            history.update(&mut game_objects, Input::default(), bounds, TICK);
+        }
+
+        // the wall rewinds with the world, the rock flies on into it
+        let rewind = Input {
+            rewind: true,
+            ..Default::default()
+        };
+        for _ in 0..120 {
+            history.update(&mut game_objects, rewind, bounds, TICK);
+            assert!(game_objects.asteroids[0].body.lin_pos.x < 400.0);
+        }
+        assert!(game_objects.asteroids[0].body.lin_vel.x < 0.0);
+    }
+}
@@ -0,0 +1,123 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{draw_text, Texture2D, GRAY, ORANGE};
+use crate::{invalid, read_or_empty};
+use crate::{Asteroid, Draw, Update};
+
+// [Scoring]
+// Breaking an asteroid is worth more the smaller and the more jagged it is.
+// Breaks that follow each other within `COMBO_WINDOW` raise a multiplier, and
+// clearing a level before its par time pays a bonus for every second left.
+const COMBO_WINDOW: f32 = 1.5;
+const MAX_COMBO: u32 = 8;
+const BONUS_PER_SECOND: f32 = 50.0;
+// Only the best few runs are kept.
+const HIGH_SCORES: usize = 10;
+
+#[derive(Clone, Default)]
+pub struct Score {
+    pub points: u32,
+    // breaks in the running chain, and the seconds left to extend it
+    pub combo: u32,
+    pub combo_timer: f32,
+    // seconds spent in the current level and the time it should take
+    pub elapsed: f32,
+    pub par: f32,
+}
+
+impl Score {
+    // Keeps the points, everything else belongs to the previous level.
+    pub fn start_level(&mut self, par: f32) {
+        *self = Score {
+            points: self.points,
+            par,
+            ..Default::default()
+        };
+    }
+
+    pub fn broke(&mut self, asteroid: &Asteroid) {
+        self.combo = (self.combo + 1).min(MAX_COMBO);
+        self.combo_timer = COMBO_WINDOW;
+        self.points += self.combo * points(asteroid);
+    }
+
+    pub fn time_bonus(&self) -> u32 {
+        ((self.par - self.elapsed).max(0.0) * BONUS_PER_SECOND) as u32
+    }
+}
+
+// The base value of breaking `asteroid`, before the combo multiplier.
+pub fn points(asteroid: &Asteroid) -> u32 {
+    let sides = asteroid.outline.len() as f32;
+    (sides * 1000.0 / asteroid.size.max(10.0)).round() as u32
+}
+
+impl Update for Score {
+    fn update(&mut self, dt: f32) {
+        self.elapsed += dt;
+        self.combo_timer = (self.combo_timer - dt).max(0.0);
+        if self.combo_timer == 0.0 {
+            self.combo = 0;
+        }
+    }
+}
+
+impl Draw for Score {
+    fn draw(&self, _spritesheet: Option<&Texture2D>) {
+        draw_text(&self.points.to_string(), 20.0, 40.0, 40.0, GRAY);
+        if self.combo > 1 {
+            draw_text(&format!("x{}", self.combo), 20.0, 70.0, 30.0, ORANGE);
+        }
+    }
+}
+
+// The best runs on this machine, highest first. Stored as one `INITIALS points`
+// line each.
+pub struct HighScores {
+    pub entries: Vec<(String, u32)>,
+}
+
+impl HighScores {
+    // A missing file is an empty table.
+    pub fn load(path: &Path) -> io::Result<HighScores> {
+        let text = read_or_empty(path)?;
+        let mut entries: Vec<(String, u32)> = vec![];
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let entry = line
+                .split_once(' ')
+                .and_then(|(initials, points)| {
+                    Some((initials.to_string(), points.trim().parse().ok()?))
+                })
+                .ok_or_else(|| invalid(&format!("`{line}` is not a high score")))?;
+            entries.push(entry);
+        }
+        entries.sort_by_key(|entry| Reverse(entry.1));
+        entries.truncate(HIGH_SCORES);
+        Ok(HighScores { entries })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text: String = self
+            .entries
+            .iter()
+            .map(|(initials, points)| format!("{initials} {points}\n"))
+            .collect();
+        fs::write(path, text)
+    }
+
+    pub fn qualifies(&self, points: u32) -> bool {
+        points > 0
+            && (self.entries.len() < HIGH_SCORES
+                || self.entries.last().is_some_and(|(_, last)| points > *last))
+    }
+
+    // Ties go below the runs that got there first.
+    pub fn insert(&mut self, initials: &str, points: u32) {
+        let at = self.entries.partition_point(|(_, other)| *other >= points);
+        self.entries.insert(at, (initials.to_string(), points));
+        self.entries.truncate(HIGH_SCORES);
+    }
+}
@@ -0,0 +1,1104 @@
+use crate::{candidates, overlapping, self_candidates, Aabb};
+use crate::{collision_across_time, contact, resolve, sweep};
+use crate::{dilate, fracture, gravity_at, TimeField, Well};
+use crate::{Advance, Field, Shape, TimeImmune, Update};
+use crate::{Asteroid, Body, Bullet, Debris, GameObjects, Input, Shadow, Ship, Smoke, DENSITY};
+use crate::{Integrator, Vec2, TAU};
+use std::ops::{Add, Mul};
+
+// [Headless]
+// Everything that moves the world forward lives here and never touches the
+// window, the keyboard or the clock. The front-end reads `Input`, measures the
+// screen and draws whatever `GameObjects` ends up as.
+
+// The simulation only ever advances by `TICK`, so the same inputs always give
+// the same world no matter the frame rate.
+pub const TICK: f32 = 1.0 / 120.0;
+
+// [Lives] seconds a wreck drifts before the next ship, and that ship's grace
+const RESPAWN_DELAY: f32 = 1.5;
+const INVULNERABLE: f32 = 2.0;
+
+#[derive(Clone, Copy)]
+pub struct Bounds {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Bounds {
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x > 0.0 && point.y > 0.0 && point.x < self.width && point.y < self.height
+    }
+
+    // What to add to `to` to get its wrapped image closest to `from`.
+    pub fn nearest_image(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let nearest = |delta: f32, size: f32| {
+            if delta > size / 2.0 {
+                -size
+            } else if delta < -size / 2.0 {
+                size
+            } else {
+                0.0
+            }
+        };
+        Vec2::new(
+            nearest(to.x - from.x, self.width),
+            nearest(to.y - from.y, self.height),
+        )
+    }
+
+    // Offsets of the wrapped images of a box poking across the edges, for drawing.
+    pub fn images(&self, aabb: &Aabb) -> Vec<Vec2> {
+        let across = |min: f32, max: f32, size: f32| {
+            let mut offsets = vec![0.0];
+            if min < 0.0 {
+                offsets.push(size);
+            }
+            if max > size {
+                offsets.push(-size);
+            }
+            offsets
+        };
+        let mut images = vec![];
+        for x in across(aabb.min.x, aabb.max.x, self.width) {
+            for y in across(aabb.min.y, aabb.max.y, self.height) {
+                if x != 0.0 || y != 0.0 {
+                    images.push(Vec2::new(x, y));
+                }
+            }
+        }
+        images
+    }
+
+    pub fn wrap(&self, body: &mut Body) {
+        let x = &mut body.lin_pos.x;
+        let y = &mut body.lin_pos.y;
+        if *x < 0.0 {
+            *x = self.width;
+        } else if *x > self.width {
+            *x = 0.0;
+        };
+        if *y < 0.0 {
+            *y = self.height;
+        } else if *y > self.height {
+            *y = 0.0;
+        };
+    }
+}
+
+// Advances the world by one step of `dt` with the player holding `input`.
+pub fn step(game_objects: &mut GameObjects, input: Input, bounds: Bounds, dt: f32) {
+    game_objects.bounds = bounds;
+    game_objects.ship.input = input;
+    game_objects.update(dt);
+}
+
+impl Update for Smoke {
+    fn update(&mut self, dt: f32) {
+        self.timer += dt * self.body.time_scale;
+    }
+}
+
+impl Advance for Debris {
+    fn advance(&mut self, dt: f32, field: &Field) {
+        self.body.advance(dt, field);
+        self.timer += dt * self.body.time_scale;
+    }
+}
+
+impl Shadow {
+    pub fn new(ship: Ship, inputs: Vec<Input>) -> Shadow {
+        Shadow {
+            ship,
+            inputs: inputs.into(),
+            tick: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.inputs.len()
+    }
+}
+
+impl Advance for Shadow {
+    fn advance(&mut self, dt: f32, field: &Field) {
+        self.ship.input = self.inputs.get(self.tick).copied().unwrap_or_default();
+        self.tick += 1;
+        self.ship.advance(dt, field);
+    }
+}
+
+impl Advance for Body {
+    fn advance(&mut self, dt: f32, field: &Field) {
+        // [TimeDilation]
+        let dt = dt * self.time_scale;
+
+        // Forces applied before the update stay constant over the step, gravity
+        // and the drag below depend on the state and are evaluated by the
+        // integrator.
+        let force = self.force / self.mass;
+        let torque = self.torque / self.inertia;
+        let profile = field.profile;
+        let wells = field.wells;
+
+        // [DragClamp]
+        self.lin_acc = integrate(
+            self.integrator,
+            &mut self.lin_pos,
+            &mut self.lin_vel,
+            dt,
+            |lin_pos: Vec2, lin_vel: Vec2| {
+                // [Gravity]
+                let force = force + gravity_at(wells, lin_pos);
+                if lin_vel.length() > profile.lin_drag_above {
+                    force - lin_vel
+                } else {
+                    force
+                }
+            },
+        );
+        self.ang_acc = integrate(
+            self.integrator,
+            &mut self.ang_pos,
+            &mut self.ang_vel,
+            dt,
+            |_, ang_vel: f32| {
+                if ang_vel.abs() > profile.ang_drag_above {
+                    torque - profile.ang_drag * ang_vel.signum()
+                } else {
+                    torque
+                }
+            },
+        );
+
+        self.force = Vec2 { x: 0.0, y: 0.0 };
+        self.torque = 0.0;
+    }
+}
+
+// Advances `pos` and `vel` by `dt` under `acceleration(pos, vel)` and returns
+// the acceleration at the start of the step. Works for linear (Vec2) and
+// angular (f32) motion alike.
+pub fn integrate<T>(
+    integrator: Integrator,
+    pos: &mut T,
+    vel: &mut T,
+    dt: f32,
+    acceleration: impl Fn(T, T) -> T,
+) -> T
+where
+    T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+{
+    let (p, v) = (*pos, *vel);
+    let a = acceleration(p, v);
+    match integrator {
+        Integrator::SemiImplicitEuler => {
+            *vel = v + a * dt;
+            *pos = p + *vel * dt;
+        }
+        Integrator::VelocityVerlet => {
+            *pos = p + v * dt + a * (dt * dt / 2.0);
+            // the velocity half is only predicted, drag needs some estimate
+            let predicted = v + a * dt;
+            let next = acceleration(*pos, predicted);
+            *vel = v + (a + next) * (dt / 2.0);
+        }
+        Integrator::Rk4 => {
+            let (k1p, k1v) = (v, a);
+            let (k2p, k2v) = (
+                v + k1v * (dt / 2.0),
+                acceleration(p + k1p * (dt / 2.0), v + k1v * (dt / 2.0)),
+            );
+            let (k3p, k3v) = (
+                v + k2v * (dt / 2.0),
+                acceleration(p + k2p * (dt / 2.0), v + k2v * (dt / 2.0)),
+            );
+            let (k4p, k4v) = (v + k3v * dt, acceleration(p + k3p * dt, v + k3v * dt));
+            *pos = p + (k1p + k2p * 2.0 + k3p * 2.0 + k4p) * (dt / 6.0);
+            *vel = v + (k1v + k2v * 2.0 + k3v * 2.0 + k4v) * (dt / 6.0);
+        }
+    }
+    a
+}
+
+impl Body {
+    // How far the last `advance` by `dt` carried the body.
+    pub fn travel(&self, dt: f32) -> Vec2 {
+        self.lin_vel * dt * self.time_scale
+    }
+}
+
+impl Advance for Ship {
+    fn advance(&mut self, dt: f32, field: &Field) {
+        let input_left = self.input.left;
+        let input_right = self.input.right;
+        let profile = field.profile;
+
+        let lin_boost: Vec2 = if self.input.thrust {
+            -profile.thrust
+                * Vec2 {
+                    x: self.body.ang_pos.cos(),
+                    y: self.body.ang_pos.sin(),
+                }
+        } else {
+            // [AutoBreak]
+            -profile.lin_brake * self.body.lin_vel
+        };
+        let ang_boost: f32 = if input_left {
+                -profile.turn
+            } else if input_right {
+                profile.turn
+            } else {
+                0.0
+            }
+            // [AutoBreak]
+             + if !input_left && !input_right {
+                    -profile.ang_brake*self.body.ang_vel
+                }
+                else{0.0};
+
+        self.body.apply_force(self.body.mass * lin_boost);
+        self.body.apply_torque(self.body.inertia * ang_boost);
+
+        // clamp
+        if self.body.lin_vel.length() > profile.max_lin_vel {
+            self.body.lin_vel =
+                profile.max_lin_vel * self.body.lin_vel / self.body.lin_vel.length();
+        }
+        if self.body.ang_vel.abs() > profile.max_ang_vel {
+            self.body.ang_vel = profile.max_ang_vel * self.body.ang_vel / self.body.ang_vel.abs();
+        }
+        self.body.advance(dt, field);
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+    }
+}
+
+impl Advance for Asteroid {
+    fn advance(&mut self, dt: f32, field: &Field) {
+        self.body.advance(dt, field);
+    }
+}
+
+impl Bullet {
+    pub fn fired_from(ship: &Ship, time_immune: bool) -> Bullet {
+        let mut bullet = Bullet {
+            body: Body {
+                lin_pos: ship.shape()[0],
+                lin_vel: -1000.0
+                    * Vec2 {
+                        x: ship.body.ang_pos.cos(),
+                        y: ship.body.ang_pos.sin(),
+                    }
+                    + 0.2 * ship.body.lin_vel,
+                ang_pos: ship.body.ang_pos + TAU / 2.0,
+                time_scale: ship.body.time_scale,
+                integrator: ship.body.integrator,
+                ..Default::default()
+            },
+            time_immune,
+        };
+        // a 10x5 rectangle, see `impl Shape for Bullet`
+        bullet.body.mass = DENSITY * 10.0 * 5.0;
+        bullet.body.inertia = bullet.body.mass * (10.0 * 10.0 + 5.0 * 5.0) / 12.0;
+        bullet
+    }
+}
+
+impl Advance for Bullet {
+    fn advance(&mut self, dt: f32, field: &Field) {
+        self.body.advance(dt, field);
+    }
+}
+
+impl Update for GameObjects {
+    fn update(&mut self, dt: f32) {
+        self.tune(dt);
+        self.dilate(dt);
+        let field = Field {
+            wells: &self.wells,
+            profile: self.profile,
+        };
+        let alive = self.respawn_in.is_none();
+        if alive {
+            self.ship.advance(dt, &field);
+        }
+        self.bullets
+            .iter_mut()
+            .for_each(|bullet| bullet.advance(dt, &field));
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| asteroid.advance(dt, &field));
+        self.smokes.iter_mut().for_each(|smoke| smoke.update(dt));
+        self.debris
+            .iter_mut()
+            .for_each(|debris| debris.advance(dt, &field));
+        self.score.update(dt);
+
+        if let Some(shadow) = &mut self.shadow {
+            shadow.advance(dt, &field);
+        }
+
+        if alive && self.ship.input.fire {
+            self.bullets
+                .push(Bullet::fired_from(&self.ship, self.immune_bullets));
+        }
+        if alive && self.ship.input.drop_field && self.field_charges > 0 {
+            self.field_charges -= 1;
+            self.time_fields
+                .push(TimeField::dropped_at(self.ship.body.lin_pos));
+        }
+        if let Some(shadow) = &self.shadow {
+            if shadow.ship.input.fire {
+                self.bullets
+                    .push(Bullet::fired_from(&shadow.ship, self.immune_bullets));
+            }
+            if shadow.is_finished() {
+                self.shadow = None;
+            }
+        }
+
+        self.bullets
+            .retain(|bullet| self.bounds.contains(bullet.body.lin_pos));
+        self.wrap();
+        self.collide(dt, false);
+        self.smokes.retain(|smoke| smoke.timer < 3.0);
+        self.debris.retain(|debris| debris.timer < 1.5);
+        self.time_fields
+            .iter_mut()
+            .for_each(|time_field| time_field.update(dt));
+        self.time_fields
+            .retain(|time_field| !time_field.is_expired());
+        if self.bounce(dt, false) {
+            self.wreck();
+        }
+        self.revive(dt);
+    }
+}
+
+impl GameObjects {
+    // Collision response between everything that is solid: asteroids with each
+    // other, ships with asteroids, and both against obstacles. Returns whether
+    // an asteroid hit the player's ship while it was vulnerable.
+    //
+    // While rewinding only pairs across timelines meet, like in `collide`. The
+    // rewinding side is restored on the next frame anyway, what lasts is how
+    // the time-immune side bounced off it. Ships never wreck then.
+    pub fn bounce(&mut self, dt: f32, rewinding: bool) -> bool {
+        let bounds = self.bounds;
+        let across = |immune1: bool, immune2: bool| !rewinding || immune1 != immune2;
+        for (it, other) in self_candidates(&self.asteroids, bounds) {
+            let (left, right) = self.asteroids.split_at_mut(other);
+            let (asteroid, other) = (&mut left[it], &mut right[0]);
+            if !across(asteroid.time_immune, other.time_immune) {
+                continue;
+            }
+            if let Some(contact) = contact(asteroid, other, bounds) {
+                resolve(&mut asteroid.body, &mut other.body, &contact);
+            }
+        }
+
+        let asteroids: Vec<Aabb> = self.asteroids.iter().map(Aabb::of).collect();
+        let obstacles: Vec<Aabb> = self.obstacles.iter().map(Aabb::of).collect();
+        let planets: Vec<Well> = self.planets();
+        let mut wrecked = false;
+        let player = self.respawn_in.is_none().then_some(&mut self.ship);
+        let ships = player.into_iter().map(|ship| (ship, true)).chain(
+            self.shadow
+                .iter_mut()
+                .map(|shadow| (&mut shadow.ship, false)),
+        );
+        for (ship, is_player) in ships {
+            // [CCD] a fast ship is put back where it first touched, a rewinding
+            // one is exactly where it was recorded
+            if !rewinding {
+                let travel = ship.body.travel(dt);
+                let swept = [Aabb::swept(ship, travel)];
+                let earliest = overlapping(&swept, &asteroids, bounds)
+                    .into_iter()
+                    .filter_map(|(_, it)| {
+                        let asteroid = &self.asteroids[it];
+                        sweep(ship, travel, asteroid, asteroid.body.travel(dt), bounds)
+                    })
+                    .chain(
+                        overlapping(&swept, &obstacles, bounds)
+                            .into_iter()
+                            .filter_map(|(_, it)| {
+                                sweep(ship, travel, &self.obstacles[it], Vec2::ZERO, bounds)
+                            }),
+                    )
+                    .map(|(time, _)| time)
+                    .fold(1.0, f32::min);
+                ship.body.lin_pos -= travel * (1.0 - earliest);
+            }
+
+            // ships always rewind
+            for (_, it) in candidates(std::slice::from_ref(ship), &self.asteroids, bounds) {
+                let asteroid = &mut self.asteroids[it];
+                if !across(false, asteroid.time_immune) {
+                    continue;
+                }
+                if let Some(contact) = contact(ship, asteroid, bounds) {
+                    resolve(&mut ship.body, &mut asteroid.body, &contact);
+                    wrecked |= is_player && !rewinding && ship.invulnerable <= 0.0;
+                }
+            }
+            for (_, it) in candidates(std::slice::from_ref(ship), &self.obstacles, bounds) {
+                let obstacle = &self.obstacles[it];
+                if !across(false, obstacle.time_immune) {
+                    continue;
+                }
+                if let Some(contact) = contact(ship, obstacle, bounds) {
+                    resolve(&mut ship.body, &mut obstacle.body(), &contact);
+                }
+            }
+            for planet in &planets {
+                if !across(false, planet.time_immune()) {
+                    continue;
+                }
+                if let Some(contact) = contact(ship, planet, bounds) {
+                    resolve(&mut ship.body, &mut planet.body(), &contact);
+                }
+            }
+        }
+
+        for (it, other) in candidates(&self.asteroids, &self.obstacles, bounds) {
+            let (asteroid, obstacle) = (&mut self.asteroids[it], &self.obstacles[other]);
+            if !across(asteroid.time_immune, obstacle.time_immune) {
+                continue;
+            }
+            if let Some(contact) = contact(asteroid, obstacle, bounds) {
+                resolve(&mut asteroid.body, &mut obstacle.body(), &contact);
+            }
+        }
+        for (it, other) in candidates(&self.asteroids, &planets, bounds) {
+            let (asteroid, planet) = (&mut self.asteroids[it], &planets[other]);
+            if !across(asteroid.time_immune, planet.time_immune()) {
+                continue;
+            }
+            if let Some(contact) = contact(asteroid, planet, bounds) {
+                resolve(&mut asteroid.body, &mut planet.body(), &contact);
+            }
+        }
+        wrecked
+    }
+
+    // [Lives]
+    // The ship falls apart into its three edges, the next one only comes after
+    // `RESPAWN_DELAY` so there is time to rewind the crash away.
+    fn wreck(&mut self) {
+        let ship = &self.ship.body;
+        let vertices = self.ship.shape();
+        for it in 0..vertices.len() {
+            let a = vertices[it];
+            let b = vertices[(it + 1) % vertices.len()];
+            let middle = (a + b) / 2.0;
+            self.debris.push(Debris {
+                body: Body {
+                    lin_pos: middle,
+                    lin_vel: ship.lin_vel + 80.0 * (middle - ship.lin_pos).normalize_or_zero(),
+                    ang_vel: 4.0 * (it as f32 - 1.0),
+                    time_scale: ship.time_scale,
+                    integrator: ship.integrator,
+                    ..Default::default()
+                },
+                half: (b - a) / 2.0,
+                timer: 0.0,
+            });
+        }
+        self.smokes.push(Smoke {
+            body: Body {
+                lin_pos: ship.lin_pos,
+                ..Default::default()
+            },
+            timer: 0.0,
+            size: 60.0,
+        });
+        self.respawn_in = Some(RESPAWN_DELAY);
+    }
+
+    // Counts down a wreck and brings in the next ship, if there is one left.
+    fn revive(&mut self, dt: f32) {
+        let Some(timer) = &mut self.respawn_in else {
+            return;
+        };
+        *timer = (*timer - dt).max(0.0);
+        if *timer > 0.0 || self.lives == 0 {
+            return;
+        }
+        self.lives -= 1;
+        self.respawn_in = None;
+        let body = Body {
+            lin_pos: self.safe_spot(),
+            ang_pos: self.ship.body.ang_pos,
+            integrator: self.ship.body.integrator,
+            ..Default::default()
+        };
+        self.ship = Ship {
+            invulnerable: INVULNERABLE,
+            ..Ship::new(body)
+        };
+    }
+
+    // The wrecked ship is not coming back.
+    pub fn is_game_over(&self) -> bool {
+        self.lives == 0 && self.respawn_in == Some(0.0)
+    }
+
+    // The point of a coarse grid farthest from every asteroid and planet, both
+    // now and once the new ship stops being invulnerable. Points inside
+    // obstacles are skipped, ties go to the first point found.
+    fn safe_spot(&self) -> Vec2 {
+        let bounds = self.bounds;
+        let distance = |from: Vec2, to: Vec2| (to + bounds.nearest_image(from, to) - from).length();
+        let clearance = |point: Vec2| {
+            let asteroids = self.asteroids.iter().map(|asteroid| {
+                let later = asteroid.body.lin_pos + asteroid.body.lin_vel * INVULNERABLE;
+                distance(point, asteroid.body.lin_pos).min(distance(point, later)) - asteroid.size
+            });
+            let planets = self
+                .planets()
+                .into_iter()
+                .map(|planet| distance(point, planet.center) - planet.radius);
+            asteroids.chain(planets).fold(f32::MAX, f32::min)
+        };
+
+        const STEP: f32 = 40.0;
+        let mut best = (f32::MIN, Vec2::new(bounds.width, bounds.height) / 2.0);
+        let mut y = STEP / 2.0;
+        while y < bounds.height {
+            let mut x = STEP / 2.0;
+            while x < bounds.width {
+                let point = Vec2::new(x, y);
+                if !self
+                    .obstacles
+                    .iter()
+                    .any(|obstacle| obstacle.rect.contains(point))
+                {
+                    let clearance = clearance(point);
+                    if clearance > best.0 {
+                        best = (clearance, point);
+                    }
+                }
+                x += STEP;
+            }
+            y += STEP;
+        }
+        best.1
+    }
+
+    // warping
+    fn wrap(&mut self) {
+        let bounds = self.bounds;
+        bounds.wrap(&mut self.ship.body);
+        if let Some(shadow) = &mut self.shadow {
+            bounds.wrap(&mut shadow.ship.body);
+        }
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| bounds.wrap(&mut asteroid.body));
+        self.bullets
+            .iter_mut()
+            .for_each(|bullet| bounds.wrap(&mut bullet.body));
+        self.smokes
+            .iter_mut()
+            .for_each(|smoke| bounds.wrap(&mut smoke.body));
+        self.debris
+            .iter_mut()
+            .for_each(|debris| bounds.wrap(&mut debris.body));
+    }
+
+    // The wells with a solid surface.
+    fn planets(&self) -> Vec<Well> {
+        self.wells
+            .iter()
+            .filter(|well| well.is_solid())
+            .cloned()
+            .collect()
+    }
+
+    // Eases the physics towards the level's profile, so that a new level never
+    // changes how the ship handles from one tick to the next.
+    fn tune(&mut self, dt: f32) {
+        self.profile = self.profile.ease(&self.profile_target, dt);
+    }
+
+    fn dilate(&mut self, dt: f32) {
+        let time_fields = &self.time_fields;
+        dilate(&mut self.ship.body, time_fields, dt);
+        if let Some(shadow) = &mut self.shadow {
+            dilate(&mut shadow.ship.body, time_fields, dt);
+        }
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| dilate(&mut asteroid.body, time_fields, dt));
+        self.bullets
+            .iter_mut()
+            .for_each(|bullet| dilate(&mut bullet.body, time_fields, dt));
+        self.smokes
+            .iter_mut()
+            .for_each(|smoke| dilate(&mut smoke.body, time_fields, dt));
+    }
+
+    // Stops bullets at obstacles and breaks the asteroids they hit. While rewinding
+    // only objects on different timelines can meet, returns whether any did.
+    pub fn collide(&mut self, dt: f32, rewinding: bool) -> bool {
+        let mut collided_any = false;
+        let mut new_asteroids: Vec<Asteroid> = vec![];
+        let bullets: Vec<Aabb> = self
+            .bullets
+            .iter()
+            .map(|bullet| Aabb::swept(bullet, bullet.body.travel(dt)))
+            .collect();
+        let obstacles: Vec<Aabb> = self.obstacles.iter().map(Aabb::of).collect();
+        let planets = self.planets();
+        let mut stopped = vec![false; self.bullets.len()];
+        for (it, other) in overlapping(&bullets, &obstacles, self.bounds) {
+            let bullet = &self.bullets[it];
+            let obstacle = &self.obstacles[other];
+            if collision_across_time(
+                bullet,
+                bullet.body.travel(dt),
+                obstacle,
+                Vec2::ZERO,
+                self.bounds,
+                rewinding,
+            )
+            .is_some()
+            {
+                stopped[it] = true;
+                collided_any = true;
+            }
+        }
+        for (it, bullet) in self.bullets.iter().enumerate() {
+            // planets never rewind, like obstacles
+            let hit = planets.iter().any(|planet| {
+                collision_across_time(
+                    bullet,
+                    bullet.body.travel(dt),
+                    planet,
+                    Vec2::ZERO,
+                    self.bounds,
+                    rewinding,
+                )
+                .is_some()
+            });
+            stopped[it] |= hit;
+            collided_any |= hit;
+        }
+        let mut stopped = stopped.into_iter();
+        self.bullets.retain(|_| !stopped.next().unwrap());
+
+        let bullets: Vec<Aabb> = self
+            .bullets
+            .iter()
+            .map(|bullet| Aabb::swept(bullet, bullet.body.travel(dt)))
+            .collect();
+        let asteroids: Vec<Aabb> = self
+            .asteroids
+            .iter()
+            .map(|asteroid| Aabb::swept(asteroid, asteroid.body.travel(dt)))
+            .collect();
+        let mut spent = vec![false; self.bullets.len()];
+        let mut near = vec![vec![]; self.asteroids.len()];
+        for (bullet, asteroid) in overlapping(&bullets, &asteroids, self.bounds) {
+            near[asteroid].push(bullet);
+        }
+        let mut near = near.into_iter();
+        self.asteroids.retain(|asteroid| {
+            let hit = near.next().unwrap().into_iter().find_map(|it| {
+                let bullet = &self.bullets[it];
+                if spent[it] {
+                    return None;
+                }
+                collision_across_time(
+                    bullet,
+                    bullet.body.travel(dt),
+                    asteroid,
+                    asteroid.body.travel(dt),
+                    self.bounds,
+                    rewinding,
+                )
+                .map(|manifold| (it, manifold.offset))
+            });
+            let Some((it, offset)) = hit else {
+                return true;
+            };
+            spent[it] = true;
+            collided_any = true;
+
+            // [Warping] the asteroid breaks where its image was hit
+            let mut bullet = self.bullets[it].clone();
+            bullet.body.lin_pos -= offset;
+            let fragments = fracture(asteroid, &bullet);
+            // a refused cut only pushes the asteroid and scores nothing
+            if fragments.len() != 1 {
+                self.score.broke(asteroid);
+                self.smokes.push(Smoke {
+                    body: Body {
+                        lin_pos: asteroid.body.lin_pos,
+                        ..Default::default()
+                    },
+                    timer: 0.0,
+                    size: 2.0 * asteroid.size,
+                });
+            }
+            new_asteroids.extend(fragments);
+            false
+        });
+        let mut spent = spent.into_iter();
+        self.bullets.retain(|_| !spent.next().unwrap());
+        self.asteroids.append(&mut new_asteroids);
+        collided_any
+    }
+
+    // Replaces the world with a recorded past, except for time-immune objects
+    // which are carried over and keep moving forward.
+    pub fn rewind_to(&mut self, past: &GameObjects, dt: f32) {
+        let mut past = past.clone();
+        past.asteroids.retain(|asteroid| !asteroid.time_immune);
+        past.bullets.retain(|bullet| !bullet.time_immune);
+        past.obstacles.retain(|obstacle| !obstacle.time_immune);
+
+        self.asteroids.retain(|asteroid| asteroid.time_immune);
+        self.bullets.retain(|bullet| bullet.time_immune);
+        self.obstacles.retain(|obstacle| obstacle.time_immune);
+        let field = Field {
+            wells: &self.wells,
+            profile: self.profile,
+        };
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| asteroid.advance(dt, &field));
+        self.bullets
+            .iter_mut()
+            .for_each(|bullet| bullet.advance(dt, &field));
+        self.bullets
+            .retain(|bullet| self.bounds.contains(bullet.body.lin_pos));
+        let bounds = self.bounds;
+        self.asteroids
+            .iter_mut()
+            .for_each(|asteroid| bounds.wrap(&mut asteroid.body));
+        self.bullets
+            .iter_mut()
+            .for_each(|bullet| bounds.wrap(&mut bullet.body));
+
+        past.asteroids.append(&mut self.asteroids);
+        past.bullets.append(&mut self.bullets);
+        past.obstacles.append(&mut self.obstacles);
+        *self = past;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::replay::checksum;
+    use crate::{Obstacle, Profile, Rect, Rng, Score, PI};
+
+    // An empty 800x600 world, the default window.
+    pub fn world() -> GameObjects {
+        GameObjects {
+            ship: Ship::new(Body::default()),
+            asteroids: vec![],
+            bullets: vec![],
+            smokes: vec![],
+            debris: vec![],
+            obstacles: vec![],
+            shadow: None,
+            casts_shadow: false,
+            immune_bullets: false,
+            time_fields: vec![],
+            field_charges: 0,
+            wells: vec![],
+            lives: 0,
+            respawn_in: None,
+            score: Score::default(),
+            profile: Profile::default(),
+            profile_target: Profile::default(),
+            bounds: Bounds {
+                width: 800.0,
+                height: 600.0,
+            },
+            seed: 0,
+        }
+    }
+
+    // The level 3 wall.
+    fn wall() -> Obstacle {
+        Obstacle {
+            rect: Rect::new(400.0, 425.0, 20.0, 150.0),
+            time_immune: false,
+        }
+    }
+
+    #[test]
+    fn ship_at_full_thrust_does_not_pass_a_wall() {
+        let mut game_objects = world();
+        game_objects.obstacles = vec![wall()];
+        game_objects.ship = Ship::new(Body {
+            lin_pos: Vec2::new(250.0, 500.0),
+            // thrust pushes along -(cos, sin), so towards +x
+            ang_pos: PI,
+            ..Default::default()
+        });
+        let input = Input {
+            thrust: true,
+            ..Default::default()
+        };
+        for _ in 0..120 {
+            let bounds = game_objects.bounds;
+            step(&mut game_objects, input, bounds, TICK);
+            assert!(game_objects.ship.body.lin_pos.x < 400.0);
+        }
+    }
+
+    #[test]
+    fn asteroid_bounces_off_a_wall() {
+        let mut game_objects = world();
+        game_objects.obstacles = vec![wall()];
+        game_objects.asteroids = vec![Asteroid::jagged(
+            Body {
+                lin_pos: Vec2::new(330.0, 500.0),
+                lin_vel: Vec2::new(200.0, 0.0),
+                ..Default::default()
+            },
+            20.0,
+            &mut Rng::new(1),
+            false,
+        )];
+        for _ in 0..240 {
+            let bounds = game_objects.bounds;
+            step(&mut game_objects, Input::default(), bounds, TICK);
+            assert!(game_objects.asteroids[0].body.lin_pos.x < 400.0);
+        }
+        assert!(game_objects.asteroids[0].body.lin_vel.x < 0.0);
+    }
+
+    // A few rocks around a planet and a ship flying on random keys, the same
+    // keys for the same `seed`.
+    fn session(seed: u64, ticks: usize) -> u64 {
+        let mut game_objects = world();
+        let mut rng = Rng::new(seed);
+        game_objects.wells = vec![Well {
+            center: Vec2::new(400.0, 300.0),
+            strength: 4e6,
+            radius: 30.0,
+        }];
+        game_objects.asteroids = (0..5)
+            .map(|it| {
+                let body = Body {
+                    lin_pos: Vec2::new(100.0 + 150.0 * it as f32, 100.0),
+                    lin_vel: Vec2::new(rng.range(-50.0, 50.0), rng.range(-50.0, 50.0)),
+                    ..Default::default()
+                };
+                Asteroid::jagged(body, 30.0, &mut rng, false)
+            })
+            .collect();
+        game_objects.ship.body.lin_pos = Vec2::new(400.0, 500.0);
+        for _ in 0..ticks {
+            let input = Input {
+                thrust: rng.next_f32() < 0.5,
+                left: rng.next_f32() < 0.3,
+                right: rng.next_f32() < 0.3,
+                fire: rng.next_f32() < 0.2,
+                ..Default::default()
+            };
+            let bounds = game_objects.bounds;
+            step(&mut game_objects, input, bounds, TICK);
+        }
+        checksum(&game_objects)
+    }
+
+    #[test]
+    fn same_inputs_give_the_same_world() {
+        assert_eq!(session(3, 1200), session(3, 1200));
+        assert_ne!(session(3, 1200), session(4, 1200));
+    }
+
+    // Worst relative change of the orbital energy of a body on an eccentric
+    // orbit around a single well, over about three orbits.
+    fn orbit_drift(integrator: Integrator, dt: f32) -> f32 {
+        let well = Well {
+            center: Vec2::new(400.0, 300.0),
+            strength: 4e6,
+            radius: 0.0,
+        };
+        let energy = |body: &Body| {
+            body.lin_vel.length_squared() / 2.0 - well.strength / well.center.distance(body.lin_pos)
+        };
+        let wells = [well.clone()];
+        let field = Field {
+            wells: &wells,
+            profile: Profile::default(),
+        };
+        let mut body = Body {
+            lin_pos: Vec2::new(600.0, 300.0),
+            // 1.2 times as fast as a circular orbit
+            lin_vel: Vec2::new(0.0, 1.2 * (4e6f32 / 200.0).sqrt()),
+            integrator,
+            ..Default::default()
+        };
+        let start = energy(&body);
+        let mut drift: f32 = 0.0;
+        for _ in 0..(60.0 / dt) as usize {
+            body.advance(dt, &field);
+            drift = drift.max(((energy(&body) - start) / start).abs());
+        }
+        drift
+    }
+
+    #[test]
+    fn euler_orbit_stays_bound() {
+        assert!(orbit_drift(Integrator::SemiImplicitEuler, TICK) < 0.01);
+    }
+
+    #[test]
+    fn verlet_orbit_drifts_less_than_euler() {
+        for dt in [TICK, 1.0 / 30.0] {
+            let euler = orbit_drift(Integrator::SemiImplicitEuler, dt);
+            assert!(orbit_drift(Integrator::VelocityVerlet, dt) < euler / 10.0);
+        }
+    }
+
+    #[test]
+    fn rk4_orbit_drifts_less_than_euler() {
+        for dt in [TICK, 1.0 / 30.0] {
+            let euler = orbit_drift(Integrator::SemiImplicitEuler, dt);
+            assert!(orbit_drift(Integrator::Rk4, dt) < euler / 10.0);
+        }
+    }
+
+    // A stalled frame worth twenty ticks, a bullet covers 170 px in it.
+    const SLOW_FRAME: f32 = 0.17;
+
+    fn bullet_at(x: f32, y: f32) -> Bullet {
+        Bullet {
+            body: Body {
+                lin_pos: Vec2::new(x, y),
+                lin_vel: Vec2::new(1000.0, 0.0),
+                ang_pos: 0.0,
+                ..Default::default()
+            },
+            time_immune: false,
+        }
+    }
+
+    #[test]
+    fn bullet_does_not_tunnel_through_a_thin_fragment() {
+        let mut game_objects = world();
+        game_objects.ship.body.lin_pos = Vec2::new(100.0, 100.0);
+        let sliver = vec![
+            Vec2::new(-2.0, -15.0),
+            Vec2::new(2.0, -15.0),
+            Vec2::new(2.0, 15.0),
+            Vec2::new(-2.0, 15.0),
+        ];
+        let mut fragment = Asteroid::from_outline(
+            Body {
+                lin_pos: Vec2::new(500.0, 300.0),
+                ..Default::default()
+            },
+            sliver,
+            false,
+        );
+        fragment.body.set_mass(&fragment.shape(), DENSITY);
+        game_objects.asteroids = vec![fragment];
+        game_objects.bullets = vec![bullet_at(400.0, 300.0)];
+
+        let bounds = game_objects.bounds;
+        step(&mut game_objects, Input::default(), bounds, SLOW_FRAME);
+        assert!(game_objects.bullets.is_empty());
+        assert!(game_objects.score.points > 0);
+    }
+
+    #[test]
+    fn bullet_does_not_tunnel_through_a_thin_wall() {
+        let mut game_objects = world();
+        game_objects.ship.body.lin_pos = Vec2::new(100.0, 100.0);
+        game_objects.obstacles = vec![Obstacle {
+            rect: Rect::new(500.0, 250.0, 4.0, 100.0),
+            time_immune: false,
+        }];
+        game_objects.bullets = vec![bullet_at(400.0, 300.0)];
+
+        let bounds = game_objects.bounds;
+        step(&mut game_objects, Input::default(), bounds, SLOW_FRAME);
+        assert!(game_objects.bullets.is_empty());
+    }
+
+    #[test]
+    fn wells_pull_on_debris() {
+        let mut game_objects = world();
+        game_objects.wells = vec![Well {
+            center: Vec2::new(400.0, 100.0),
+            strength: 4e6,
+            radius: 0.0,
+        }];
+        game_objects.ship.body.lin_pos = Vec2::new(400.0, 300.0);
+        game_objects.wreck();
+        let bounds = game_objects.bounds;
+        step(&mut game_objects, Input::default(), bounds, TICK);
+        let before: Vec<Vec2> = game_objects
+            .debris
+            .iter()
+            .map(|it| it.body.lin_vel)
+            .collect();
+        step(&mut game_objects, Input::default(), bounds, TICK);
+        for (debris, before) in game_objects.debris.iter().zip(before) {
+            assert!(debris.body.lin_vel.y < before.y);
+        }
+    }
+
+    #[test]
+    fn spin_past_the_drag_threshold_slows_down() {
+        let profile = Profile::default();
+        let mut body = Body {
+            ang_vel: 2.0 * profile.ang_drag_above,
+            ..Default::default()
+        };
+        let mut previous = body.ang_vel;
+        for _ in 0..60 {
+            body.advance(
+                TICK,
+                &Field {
+                    wells: &[],
+                    profile,
+                },
+            );
+            assert!(body.ang_vel < previous);
+            previous = body.ang_vel;
+        }
+        assert!(body.ang_vel > profile.ang_drag_above);
+    }
+
+    #[test]
+    fn grazing_hit_that_does_not_break_scores_nothing() {
+        let mut game_objects = world();
+        game_objects.ship.body.lin_pos = Vec2::new(100.0, 100.0);
+        let square = vec![
+            Vec2::new(-50.0, -50.0),
+            Vec2::new(50.0, -50.0),
+            Vec2::new(50.0, 50.0),
+            Vec2::new(-50.0, 50.0),
+        ];
+        let mut asteroid = Asteroid::from_outline(
+            Body {
+                lin_pos: Vec2::new(400.0, 300.0),
+                ..Default::default()
+            },
+            square,
+            false,
+        );
+        asteroid.body.set_mass(&asteroid.shape(), DENSITY);
+        game_objects.asteroids = vec![asteroid];
+        // the cut would only take a 2 px sliver off the top
+        game_objects.bullets = vec![bullet_at(345.0, 252.0)];
+
+        let bounds = game_objects.bounds;
+        step(&mut game_objects, Input::default(), bounds, TICK);
+        assert!(game_objects.bullets.is_empty());
+        assert_eq!(game_objects.asteroids.len(), 1);
+        assert_eq!(game_objects.score.points, 0);
+        assert_eq!(game_objects.score.combo, 0);
+    }
+}
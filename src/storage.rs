@@ -0,0 +1,20 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// [Storage]
+// What every file the game reads has in common: broken contents are reported
+// as `InvalidData`, and the tables it keeps across runs start out as no file.
+
+pub fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// A missing file reads as empty.
+pub fn read_or_empty(path: &Path) -> io::Result<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(error) => Err(error),
+    }
+}